@@ -1,27 +1,165 @@
-use std::{collections::HashSet, fs::read_to_string};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    fs::read_to_string,
+};
 
 fn main() {
     let input = read_to_string("inputs/day-03.txt").expect("missing input file");
     let rucksacks: Vec<_> = input.split('\n').filter(|s| &"" != s).collect();
+    report_problems(&rucksacks);
     println!("Part 1: {}", priority_sum(&rucksacks));
     println!("Part 2: {}", badge_priority_sum(&rucksacks));
 }
 
+// Invalid rucksacks have no single misplaced item, so they are left out of the
+// sum. `report_problems` is what tells about them.
 fn priority_sum(inputs: &[&str]) -> usize {
     inputs
         .iter()
-        .map(|line| priority(rucksack_misplaced(line)))
+        .filter_map(|line| validate(line).ok())
+        .map(priority)
         .sum()
 }
 
-fn rucksack_misplaced(line: &str) -> char {
+#[derive(Debug)]
+enum Problem {
+    OddLength(usize),
+    InvalidItem(char),
+    NoDuplicate,
+    MultipleDuplicates(Vec<char>),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OddLength(len) => write!(f, "odd number of items ({len})"),
+            Self::InvalidItem(c) => write!(f, "'{c}' is not a valid item"),
+            Self::NoDuplicate => write!(f, "no item in both compartments"),
+            Self::MultipleDuplicates(items) => write!(
+                f,
+                "multiple items in both compartments ({})",
+                items.iter().collect::<String>()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Compartment {
+    First,
+    Second,
+}
+
+#[derive(Debug)]
+struct Repair {
+    item: char,
+    amount: usize,
+    from: Compartment,
+    to: Compartment,
+}
+
+fn compartments(line: &str) -> (HashMap<char, usize>, HashMap<char, usize>) {
     let chars = line.chars().collect::<Vec<_>>();
-    let sack_a = HashSet::<&char>::from_iter(&chars[..chars.len() / 2]);
-    let sack_b = HashSet::<&char>::from_iter(&chars[chars.len() / 2..]);
-    **(sack_a
-        .intersection(&sack_b)
-        .next()
-        .expect("no common items"))
+    let count = |items: &[char]| {
+        items.iter().fold(HashMap::new(), |mut counts, c| {
+            *counts.entry(*c).or_insert(0) += 1;
+            counts
+        })
+    };
+    (
+        count(&chars[..chars.len() / 2]),
+        count(&chars[chars.len() / 2..]),
+    )
+}
+
+fn shared_items(line: &str) -> Vec<char> {
+    let (sack_a, sack_b) = compartments(line);
+    let mut shared = sack_a
+        .keys()
+        .filter(|c| sack_b.contains_key(c))
+        .cloned()
+        .collect::<Vec<_>>();
+    shared.sort();
+    shared
+}
+
+fn validate(line: &str) -> Result<char, Problem> {
+    if let Some(c) = line.chars().find(|c| !c.is_ascii_alphabetic()) {
+        return Err(Problem::InvalidItem(c));
+    }
+    if !line.len().is_multiple_of(2) {
+        return Err(Problem::OddLength(line.len()));
+    }
+    match &shared_items(line)[..] {
+        [] => Err(Problem::NoDuplicate),
+        [c] => Ok(*c),
+        shared => Err(Problem::MultipleDuplicates(shared.to_vec())),
+    }
+}
+
+// For every item type found in both compartments, move the copies from the
+// side holding fewer of them, which is the least amount of items to touch.
+// Moves only go one way for each item, so the compartments can end up with
+// different sizes; see `sizes_after`.
+fn repairs(line: &str) -> Vec<Repair> {
+    let (sack_a, sack_b) = compartments(line);
+    shared_items(line)
+        .into_iter()
+        .map(|item| {
+            let (in_a, in_b) = (sack_a[&item], sack_b[&item]);
+            if in_a <= in_b {
+                Repair {
+                    item,
+                    amount: in_a,
+                    from: Compartment::First,
+                    to: Compartment::Second,
+                }
+            } else {
+                Repair {
+                    item,
+                    amount: in_b,
+                    from: Compartment::Second,
+                    to: Compartment::First,
+                }
+            }
+        })
+        .collect()
+}
+
+// How many items each compartment holds once the repairs are done.
+fn sizes_after(line: &str, repairs: &[Repair]) -> (usize, usize) {
+    let half = line.len() / 2;
+    repairs
+        .iter()
+        .fold((half, half), |(a, b), repair| match repair.from {
+            Compartment::First => (a - repair.amount, b + repair.amount),
+            Compartment::Second => (a + repair.amount, b - repair.amount),
+        })
+}
+
+fn report_problems(inputs: &[&str]) {
+    for (i, line) in inputs.iter().enumerate() {
+        if let Err(problem) = validate(line) {
+            println!("Rucksack {}: {problem}", i + 1);
+            if let Problem::MultipleDuplicates(_) = problem {
+                let repairs = repairs(line);
+                for Repair {
+                    item,
+                    amount,
+                    from,
+                    to,
+                } in &repairs
+                {
+                    println!("  move {amount} x '{item}' from {from:?} to {to:?}");
+                }
+                let (first, second) = sizes_after(line, &repairs);
+                if first != second {
+                    println!("  compartments end up with {first} and {second} items");
+                }
+            }
+        }
+    }
 }
 
 fn priority(c: char) -> usize {
@@ -1,6 +1,14 @@
-use std::fs::read_to_string;
+use std::{env, fs::read_to_string};
 
 fn main() {
+    let crowded_limit = match &env::args().skip(1).collect::<Vec<_>>()[..] {
+        [] => None,
+        [flag, k] if flag == "--crowded" => Some(
+            k.parse::<usize>()
+                .expect("--crowded should be followed by a number"),
+        ),
+        _ => panic!("usage: day-04 [--crowded <k>]"),
+    };
     let input = read_to_string("inputs/day-04.txt").expect("missing input file");
     let assignments: Vec<_> = input
         .split('\n')
@@ -17,6 +25,22 @@ fn main() {
         .collect();
     println!("Part 1: {}", count_containing(&assignments));
     println!("Part 2: {}", count_overlapping(&assignments));
+    let coverage = coverage(&assignments);
+    println!(
+        "Uncovered sections: {}",
+        format_ranges(&sections_covered_by(&coverage, |elves| elves == 0))
+    );
+    if let Some(k) = crowded_limit {
+        println!(
+            "Sections covered by more than {k} elves: {}",
+            format_ranges(&sections_covered_by(&coverage, |elves| elves > k))
+        );
+    }
+    println!("Max overlap: {}", max_overlap(&coverage));
+    println!(
+        "Overlapping pairs across lines: {}",
+        overlapping_across_lines(&assignments).len()
+    );
 }
 
 struct Assignment {
@@ -61,3 +85,107 @@ fn count_containing(assignments: &[Pair]) -> usize {
 fn count_overlapping(assignments: &[Pair]) -> usize {
     assignments.iter().filter(|(a1, a2)| a1.overlaps(a2)).count()
 }
+
+// Identifies an elf by the line of its pair and its position within that pair.
+type ElfId = (usize, usize);
+
+#[derive(Debug, PartialEq, Eq)]
+struct Coverage {
+    from: usize,
+    to: usize,
+    elves: usize,
+}
+
+fn sweep_events(assignments: &[Pair]) -> Vec<(usize, bool)> {
+    let mut events = assignments
+        .iter()
+        .flat_map(|(a1, a2)| [a1, a2])
+        .flat_map(|a| [(a.from, true), (a.to + 1, false)])
+        .collect::<Vec<_>>();
+    events.sort();
+    events
+}
+
+// Splits the sections, from the first one up to the last assigned, into
+// contiguous ranges covered by the same number of elves.
+fn coverage(assignments: &[Pair]) -> Vec<Coverage> {
+    let mut ranges: Vec<Coverage> = vec![];
+    let mut elves = 0;
+    let mut position = 1;
+    for (section, starts) in sweep_events(assignments) {
+        if section > position {
+            match ranges.last_mut() {
+                Some(last) if last.elves == elves => last.to = section - 1,
+                _ => ranges.push(Coverage {
+                    from: position,
+                    to: section - 1,
+                    elves,
+                }),
+            }
+            position = section;
+        }
+        if starts {
+            elves += 1;
+        } else {
+            elves -= 1;
+        }
+    }
+    ranges
+}
+
+fn sections_covered_by(
+    coverage: &[Coverage],
+    predicate: impl Fn(usize) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for c in coverage.iter().filter(|c| predicate(c.elves)) {
+        match ranges.last_mut() {
+            Some(last) if last.1 + 1 == c.from => last.1 = c.to,
+            _ => ranges.push((c.from, c.to)),
+        }
+    }
+    ranges
+}
+
+fn max_overlap(coverage: &[Coverage]) -> usize {
+    coverage.iter().map(|c| c.elves).max().unwrap_or(0)
+}
+
+fn overlapping_across_lines(assignments: &[Pair]) -> Vec<(ElfId, ElfId)> {
+    let mut elves = assignments
+        .iter()
+        .enumerate()
+        .flat_map(|(line, (a1, a2))| [((line, 0), a1), ((line, 1), a2)])
+        .collect::<Vec<_>>();
+    elves.sort_by_key(|(_, a)| a.from);
+    let mut active: Vec<(ElfId, &Assignment)> = vec![];
+    let mut pairs = vec![];
+    for (id, assignment) in elves {
+        active.retain(|(_, a)| a.to >= assignment.from);
+        pairs.extend(
+            active
+                .iter()
+                .filter(|(other, _)| other.0 != id.0)
+                .map(|(other, _)| (*other, id)),
+        );
+        active.push((id, assignment));
+    }
+    pairs
+}
+
+fn format_ranges(ranges: &[(usize, usize)]) -> String {
+    if ranges.is_empty() {
+        return "none".to_string();
+    }
+    ranges
+        .iter()
+        .map(|(from, to)| {
+            if from == to {
+                format!("{from}")
+            } else {
+                format!("{from}-{to}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
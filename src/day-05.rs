@@ -1,16 +1,58 @@
-use std::fs::read_to_string;
+use std::{
+    env,
+    fmt::{self, Display},
+    fs::read_to_string,
+};
 
 fn main() {
     let input = read_to_string("inputs/day-05.txt").expect("missing input file");
-    let mut parts = input.trim().split("\n\n");
-    let mut stacks_v9000 =
-        parse_initial_state(parts.next().expect("should have initial state section"));
-    let mut stacks_v9001 = stacks_v9000.to_vec();
+    let mut parts = input.trim_end().split("\n\n");
+    let stacks = parse_initial_state(parts.next().expect("should have initial state section"));
     let moves = parse_moves(parts.next().expect("should have list of moves"));
-    apply_moves(&moves, &mut stacks_v9000, Version::V9000);
-    apply_moves(&moves, &mut stacks_v9001, Version::V9001);
-    println!("Part 1: {}", top_crates(&stacks_v9000));
-    println!("Part 2: {}", top_crates(&stacks_v9001));
+    let mut yard_v9000 = CraneYard::new(stacks.to_vec(), Box::new(CrateMover9000));
+    let mut yard_v9001 = CraneYard::new(stacks.to_vec(), Box::new(CrateMover9001));
+    for yard in [&mut yard_v9000, &mut yard_v9001] {
+        if let Err(err) = yard.apply_moves(&moves) {
+            panic!("{} failed: {err}", yard.crane.name());
+        }
+    }
+    println!("Part 1: {}", yard_v9000.top_crates());
+    println!("Part 2: {}", yard_v9001.top_crates());
+
+    // `--crane <model>` runs an extra crane model, `--step <n>` prints every
+    // yard as it was after that many moves.
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let mut yards = vec![yard_v9000, yard_v9001];
+    let mut step = None;
+    for arg in args.chunks(2) {
+        match arg {
+            [flag, model] if flag == "--crane" => {
+                let mut yard = CraneYard::new(stacks.to_vec(), parse_crane(model));
+                match yard.apply_moves(&moves) {
+                    Ok(()) => println!("{}: {}", yard.crane.name(), yard.top_crates()),
+                    Err(err) => println!("{}: {err}", yard.crane.name()),
+                }
+                yards.push(yard);
+            }
+            [flag, n] if flag == "--step" => {
+                step = Some(n.parse::<usize>().expect("step should be a number"))
+            }
+            other => panic!("unexpected arguments {other:?}"),
+        }
+    }
+    if let Some(step) = step {
+        for yard in yards.iter_mut() {
+            if let Err(err) = yard.replay_to(&moves, step) {
+                println!("\n{}: {err}", yard.crane.name());
+            }
+            println!(
+                "\n{} after {} moves:\n{}",
+                yard.crane.name(),
+                yard.step(),
+                yard.render()
+            );
+        }
+    }
 }
 
 macro_rules! next_num {
@@ -43,12 +85,110 @@ fn parse_initial_state(raw_state: &str) -> Vec<Vec<char>> {
     stacks
 }
 
-#[derive(PartialEq, Eq)]
-enum Version {
-    V9000,
-    V9001,
+trait CraneModel {
+    fn name(&self) -> String;
+
+    // Splits moving `amount` crates into the lifts the crane performs, in
+    // order. Each lift keeps the order of the crates it carries.
+    fn lifts(&self, amount: usize) -> Vec<usize>;
+
+    // The most crates the crane can carry in a single lift, if limited.
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+}
+
+struct CrateMover9000;
+
+impl CraneModel for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn lifts(&self, amount: usize) -> Vec<usize> {
+        vec![1; amount]
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+struct CrateMover9001;
+
+impl CraneModel for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_string()
+    }
+
+    fn lifts(&self, amount: usize) -> Vec<usize> {
+        vec![amount]
+    }
+}
+
+// Moves `size` crates at a time, with a smaller final lift if needed.
+struct BatchCrane {
+    size: usize,
+}
+
+impl CraneModel for BatchCrane {
+    fn name(&self) -> String {
+        format!("Batch crane ({})", self.size)
+    }
+
+    fn lifts(&self, amount: usize) -> Vec<usize> {
+        let mut lifts = vec![self.size; amount / self.size];
+        match amount % self.size {
+            0 => (),
+            remainder => lifts.push(remainder),
+        }
+        lifts
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.size)
+    }
+}
+
+// Moves everything at once like the 9001, but only up to a given capacity.
+struct LimitedCrane {
+    capacity: usize,
+}
+
+impl CraneModel for LimitedCrane {
+    fn name(&self) -> String {
+        format!("Limited crane ({})", self.capacity)
+    }
+
+    fn lifts(&self, amount: usize) -> Vec<usize> {
+        vec![amount]
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+}
+
+fn parse_crane(model: &str) -> Box<dyn CraneModel> {
+    // a crane that lifts nothing would never finish a move.
+    let positive = |n: &str, what: &str| match n.parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => panic!("{what} should be a number above zero, got {n}"),
+    };
+    match model.split(':').collect::<Vec<_>>()[..] {
+        ["9000"] => Box::new(CrateMover9000),
+        ["9001"] => Box::new(CrateMover9001),
+        ["batch", size] => Box::new(BatchCrane {
+            size: positive(size, "batch size"),
+        }),
+        ["limit", capacity] => Box::new(LimitedCrane {
+            capacity: positive(capacity, "capacity"),
+        }),
+        _ => panic!("unknown crane model {model}"),
+    }
 }
 
+// amount, from and to, with stacks numbered from 1 as in the input.
 type Move = (usize, usize, usize);
 
 fn parse_moves(raw_moves: &str) -> Vec<Move> {
@@ -56,25 +196,185 @@ fn parse_moves(raw_moves: &str) -> Vec<Move> {
         .split('\n')
         .map(|line| {
             let mut parts = line.split(' ');
-            (next_num!(parts), next_num!(parts) - 1, next_num!(parts) - 1)
+            (next_num!(parts), next_num!(parts), next_num!(parts))
         })
         .collect()
 }
 
-fn apply_moves(moves: &Vec<Move>, stacks: &mut [Vec<char>], version: Version) {
-    for (amount, from, to) in moves {
-        let split_at = stacks[*from].len() - amount;
-        let split_off = stacks[*from].split_off(split_at).into_iter();
-        match version {
-            Version::V9000 => stacks[*to].extend(split_off.rev()),
-            Version::V9001 => stacks[*to].extend(split_off),
+// Moves are numbered from 1, in the order they are applied.
+#[derive(Debug, PartialEq, Eq)]
+enum MoveError {
+    NoSuchStack {
+        move_number: usize,
+        stack: usize,
+    },
+    NotEnoughCrates {
+        move_number: usize,
+        stack: usize,
+        size: usize,
+        amount: usize,
+    },
+    OverCapacity {
+        move_number: usize,
+        lift: usize,
+        capacity: usize,
+    },
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoSuchStack { move_number, stack } => {
+                write!(f, "move {move_number}: there is no stack {stack}")
+            }
+            Self::NotEnoughCrates {
+                move_number,
+                stack,
+                size,
+                amount,
+            } => write!(
+                f,
+                "move {move_number}: stack {stack} has {size} crates, can't move {amount}"
+            ),
+            Self::OverCapacity {
+                move_number,
+                lift,
+                capacity,
+            } => write!(
+                f,
+                "move {move_number}: can't lift {lift} crates, capacity is {capacity}"
+            ),
         }
     }
 }
 
-fn top_crates(stacks: &[Vec<char>]) -> String {
-    stacks
-        .iter()
-        .map(|stack| stack.last().expect("should not be empty"))
-        .collect()
+struct CraneYard {
+    stacks: Vec<Vec<char>>,
+    crane: Box<dyn CraneModel>,
+    // moves applied so far, most recent last, with their stack indices and the
+    // crates taken from the source stack so they can be put back in reverse.
+    history: Vec<(Move, (usize, usize), Vec<char>)>,
+}
+
+impl CraneYard {
+    fn new(stacks: Vec<Vec<char>>, crane: Box<dyn CraneModel>) -> Self {
+        Self {
+            stacks,
+            crane,
+            history: vec![],
+        }
+    }
+
+    fn step(&self) -> usize {
+        self.history.len()
+    }
+
+    fn apply_moves(&mut self, moves: &[Move]) -> Result<(), MoveError> {
+        for mv in moves {
+            self.apply(*mv)?;
+        }
+        Ok(())
+    }
+
+    // Checks the move against the current state before touching any stack, so
+    // a failed move leaves the yard as it was.
+    fn apply(&mut self, mv: Move) -> Result<(), MoveError> {
+        let (amount, from, to) = mv;
+        let move_number = self.step() + 1;
+        let from = self.stack_index(move_number, from)?;
+        let to = self.stack_index(move_number, to)?;
+        let size = self.stacks[from].len();
+        if amount > size {
+            return Err(MoveError::NotEnoughCrates {
+                move_number,
+                stack: mv.1,
+                size,
+                amount,
+            });
+        }
+        let lifts = self.crane.lifts(amount);
+        if let Some(capacity) = self.crane.capacity() {
+            if let Some(lift) = lifts.iter().find(|lift| **lift > capacity) {
+                return Err(MoveError::OverCapacity {
+                    move_number,
+                    lift: *lift,
+                    capacity,
+                });
+            }
+        }
+        let taken = self.stacks[from].split_off(size - amount);
+        let mut remaining = &taken[..];
+        for lift in lifts {
+            let (rest, lifted) = remaining.split_at(remaining.len() - lift);
+            self.stacks[to].extend_from_slice(lifted);
+            remaining = rest;
+        }
+        self.history.push((mv, (from, to), taken));
+        Ok(())
+    }
+
+    fn stack_index(&self, move_number: usize, stack: usize) -> Result<usize, MoveError> {
+        match stack.checked_sub(1) {
+            Some(index) if index < self.stacks.len() => Ok(index),
+            _ => Err(MoveError::NoSuchStack { move_number, stack }),
+        }
+    }
+
+    fn undo(&mut self) -> Option<Move> {
+        let (mv, (from, to), taken) = self.history.pop()?;
+        let split_at = self.stacks[to].len() - taken.len();
+        self.stacks[to].truncate(split_at);
+        self.stacks[from].extend(taken);
+        Some(mv)
+    }
+
+    // Moves the yard to the state after the first `step` moves, assuming the
+    // moves applied so far are a prefix of `moves`. Stops at the first invalid
+    // move, leaving the yard at the last valid state.
+    fn replay_to(&mut self, moves: &[Move], step: usize) -> Result<(), MoveError> {
+        let step = step.min(moves.len());
+        while self.step() > step {
+            self.undo();
+        }
+        self.apply_moves(&moves[self.step()..step])
+    }
+
+    fn tops(&self) -> Vec<Option<char>> {
+        self.stacks
+            .iter()
+            .map(|stack| stack.last().cloned())
+            .collect()
+    }
+
+    // Empty stacks show up as '_' so every stack keeps its position.
+    fn top_crates(&self) -> String {
+        self.tops()
+            .into_iter()
+            .map(|top| top.unwrap_or('_'))
+            .collect()
+    }
+
+    fn render(&self) -> String {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        let mut lines = (0..height)
+            .rev()
+            .map(|level| {
+                self.stacks
+                    .iter()
+                    .map(|stack| match stack.get(level) {
+                        Some(c) => format!("[{c}]"),
+                        None => "   ".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+        lines.push(
+            (1..=self.stacks.len())
+                .map(|i| format!(" {i} "))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        lines.join("\n")
+    }
 }
@@ -1,35 +1,269 @@
 use std::{
     collections::{hash_map::Entry, HashMap, VecDeque},
-    fs::read_to_string,
+    env,
+    fs::{read_to_string, File},
+    io::{self, Read},
 };
 
 const MARKER_SIZE: usize = 4;
 const PACKET_SIZE: usize = 14;
+const CHUNK_SIZE: usize = 8192;
 
 fn main() {
-    let input = read_to_string("inputs/day-06.txt").expect("missing input file");
-    println!("Part 1: {}", find_marker(&input, MARKER_SIZE));
-    println!("Part 2: {}", find_marker(&input, PACKET_SIZE));
-}
-
-fn find_marker(input: &str, size: usize) -> usize {
-    let mut buffer = VecDeque::with_capacity(size + 1);
-    let mut index = HashMap::new();
-    for (i, c) in input.chars().enumerate() {
-        buffer.push_back(c);
-        index.entry(c).and_modify(|e| *e += 1).or_insert(1);
-        if buffer.len() > size {
-            let to_remove = buffer.pop_front().expect("should have elements");
-            match index.entry(to_remove).and_modify(|e| *e -= 1) {
-                Entry::Occupied(e) if *e.get() == 0 => {
-                    e.remove();
+    let sizes = [MARKER_SIZE, PACKET_SIZE];
+    let markers = match env::args().nth(1).as_deref() {
+        None => {
+            let file = File::open("inputs/day-06.txt").expect("missing input file");
+            stream_markers(file, &sizes).expect("should read input file")
+        }
+        // reads the datastream from stdin, so it can be piped from elsewhere.
+        Some("-") => stream_markers(io::stdin().lock(), &sizes).expect("should read stdin"),
+        Some("all") => {
+            let input = read_to_string("inputs/day-06.txt").expect("missing input file");
+            for (size, position) in MarkerScanner::new(input.chars(), &sizes) {
+                println!("{size}\t{position}");
+            }
+            return;
+        }
+        Some(_) => panic!("usage: day-06 [- | all]"),
+    };
+    println!("Part 1: {}", format_marker(markers[0]));
+    println!("Part 2: {}", format_marker(markers[1]));
+}
+
+fn format_marker(marker: Option<u64>) -> String {
+    marker.map_or("no marker found".to_string(), |m| m.to_string())
+}
+
+// Walks the stream once, yielding `(size, position)` for every position where
+// the last `size` characters are all different. Positions count characters
+// read so far, like the puzzle answers do.
+struct MarkerScanner<T: Iterator<Item = char>> {
+    stream: T,
+    sizes: Vec<usize>,
+    longest: usize,
+    buffer: VecDeque<char>,
+    // characters in the current window of each size, and how often they occur.
+    windows: Vec<HashMap<char, usize>>,
+    position: usize,
+    found: VecDeque<(usize, usize)>,
+}
+
+impl<T: Iterator<Item = char>> MarkerScanner<T> {
+    fn new(stream: T, sizes: &[usize]) -> Self {
+        let longest = sizes.iter().max().cloned().unwrap_or(0);
+        Self {
+            stream,
+            sizes: sizes.to_vec(),
+            longest,
+            buffer: VecDeque::with_capacity(longest + 1),
+            windows: vec![HashMap::new(); sizes.len()],
+            position: 0,
+            found: VecDeque::new(),
+        }
+    }
+
+    fn read(&mut self, c: char) {
+        self.buffer.push_back(c);
+        self.position += 1;
+        for (size, window) in self.sizes.iter().zip(self.windows.iter_mut()) {
+            window.entry(c).and_modify(|e| *e += 1).or_insert(1);
+            if self.buffer.len() > *size {
+                let to_remove = self.buffer[self.buffer.len() - 1 - size];
+                match window.entry(to_remove).and_modify(|e| *e -= 1) {
+                    Entry::Occupied(e) if *e.get() == 0 => {
+                        e.remove();
+                    }
+                    _ => (),
                 }
-                _ => (),
             }
+            if window.keys().len() == *size {
+                self.found.push_back((*size, self.position));
+            }
+        }
+        // keep just enough to know what leaves the longest window.
+        if self.buffer.len() > self.longest {
+            self.buffer.pop_front();
+        }
+    }
+}
+
+impl<T: Iterator<Item = char>> Iterator for MarkerScanner<T> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.found.is_empty() {
+            let c = self.stream.next()?;
+            self.read(c);
+        }
+        self.found.pop_front()
+    }
+}
+
+// Finds the first marker of each size in a stream of bytes, reading it in
+// chunks so it never has to be held in memory. Offsets are in bytes.
+fn stream_markers(mut reader: impl Read, sizes: &[usize]) -> io::Result<Vec<Option<u64>>> {
+    let mut detectors = sizes
+        .iter()
+        .map(|size| ByteMarkerDetector::new(*size))
+        .collect::<Vec<_>>();
+    let mut firsts = vec![None; sizes.len()];
+    let mut offset = 0;
+    let mut chunk = [0; CHUNK_SIZE];
+    while firsts.iter().any(Option::is_none) {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for byte in &chunk[..read] {
+            offset += 1;
+            for (detector, first) in detectors.iter_mut().zip(firsts.iter_mut()) {
+                if detector.push(*byte) && first.is_none() {
+                    *first = Some(offset);
+                }
+            }
+        }
+    }
+    Ok(firsts)
+}
+
+struct ByteMarkerDetector {
+    size: usize,
+    // how many of each byte value are in the window.
+    counts: [usize; 256],
+    distinct: usize,
+    // ring buffer with the last `size` bytes, oldest at `read % size`.
+    window: Vec<u8>,
+    read: usize,
+}
+
+impl ByteMarkerDetector {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            counts: [0; 256],
+            distinct: 0,
+            window: vec![0; size],
+            read: 0,
+        }
+    }
+
+    // Returns whether the last `size` bytes, including this one, are all different.
+    fn push(&mut self, byte: u8) -> bool {
+        if self.size == 0 {
+            return true;
+        }
+        let slot = self.read % self.size;
+        if self.read >= self.size {
+            let leaving = self.window[slot] as usize;
+            self.counts[leaving] -= 1;
+            if self.counts[leaving] == 0 {
+                self.distinct -= 1;
+            }
+        }
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.distinct += 1;
         }
-        if index.keys().len() == size {
-            return i + 1;
+        self.read += 1;
+        self.distinct == self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: [&str; 3] = [
+        "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        "nppdvjthqldpwncqszvftbrmjlhg",
+        "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+    ];
+
+    // Finds the first marker of each size, in a single pass over the input.
+    fn first_markers(input: &str, sizes: &[usize]) -> Vec<Option<usize>> {
+        let mut firsts = vec![None; sizes.len()];
+        for (size, position) in MarkerScanner::new(input.chars(), sizes) {
+            // a size may be asked for more than once, and each asks for its own.
+            for (first, _) in firsts.iter_mut().zip(sizes).filter(|(_, s)| **s == size) {
+                first.get_or_insert(position);
+            }
+            if firsts.iter().all(Option::is_some) {
+                break;
+            }
         }
+        firsts
+    }
+
+    // Reads at most a few bytes at a time, so windows cross chunk boundaries.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..read].copy_from_slice(&self.data[..read]);
+            self.data = &self.data[read..];
+            Ok(read)
+        }
+    }
+
+    fn as_bytes(markers: Vec<Option<usize>>) -> Vec<Option<u64>> {
+        markers.into_iter().map(|m| m.map(|m| m as u64)).collect()
+    }
+
+    #[test]
+    fn samples() {
+        let expected = [(7, 19), (6, 23), (11, 26)];
+        let sizes = [MARKER_SIZE, PACKET_SIZE];
+        for (sample, (marker, packet)) in SAMPLES.iter().zip(expected) {
+            let markers = vec![Some(marker), Some(packet)];
+            assert_eq!(first_markers(sample, &sizes), markers);
+            assert_eq!(
+                stream_markers(sample.as_bytes(), &sizes).unwrap(),
+                as_bytes(markers)
+            );
+        }
+    }
+
+    #[test]
+    fn no_marker() {
+        let sizes = [MARKER_SIZE, PACKET_SIZE];
+        let stream = "abcabcabcabcabcabc";
+        assert_eq!(first_markers(stream, &sizes), vec![None, None]);
+        assert_eq!(
+            stream_markers(stream.as_bytes(), &sizes).unwrap(),
+            vec![None, None]
+        );
+    }
+
+    #[test]
+    fn short_reads() {
+        let sizes = [MARKER_SIZE, PACKET_SIZE, 3];
+        for sample in SAMPLES {
+            for chunk in 1..=3 {
+                let reader = Trickle {
+                    data: sample.as_bytes(),
+                    chunk,
+                };
+                assert_eq!(
+                    stream_markers(reader, &sizes).unwrap(),
+                    as_bytes(first_markers(sample, &sizes))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_sizes() {
+        assert_eq!(
+            first_markers(SAMPLES[0], &[MARKER_SIZE, MARKER_SIZE]),
+            vec![Some(7), Some(7)]
+        );
     }
-    panic!("no marker found")
 }
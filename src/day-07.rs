@@ -1,6 +1,8 @@
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    env,
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
 };
 
 const DIR_SIZE_LIMIT: usize = 100_000;
@@ -8,68 +10,343 @@ const TOTAL_SIZE: usize = 70_000_000;
 const SPACE_NEEDED: usize = 30_000_000;
 
 fn main() {
-    let file = File::open("inputs/day-07.txt").expect("missing input file");
-    let mut lines = BufReader::new(file)
-        .lines()
-        .map(|res| res.expect("should not fail reading line"));
-    let entries = match Entry::from_iter(&mut lines) {
-        Entry::Dir(entries) => entries,
-        Entry::File(_) => panic!("only found one file!"),
+    let mut config = Config {
+        dir_size_limit: DIR_SIZE_LIMIT,
+        total_size: TOTAL_SIZE,
+        space_needed: SPACE_NEEDED,
     };
-    let (disk_used, dir_sizes) = dir_sizes(&entries);
-    let need_to_free = SPACE_NEEDED - (TOTAL_SIZE - disk_used);
+    let mut disk_path = None;
+    let mut command = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next_size = || {
+            args.next()
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or_else(|| panic!("{arg} should be followed by a size"))
+        };
+        match arg.as_str() {
+            "--limit" => config.dir_size_limit = next_size(),
+            "--total" => config.total_size = next_size(),
+            "--needed" => config.space_needed = next_size(),
+            "--disk" => disk_path = Some(args.next().expect("--disk should be followed by a path")),
+            _ => command.push(arg),
+        }
+    }
+    let root = match disk_path {
+        Some(path) => {
+            let mut skipped = vec![];
+            let root = Entry::from_dir(Path::new(&path), &mut skipped)
+                .expect("should be able to read the directory");
+            for (path, error) in skipped {
+                eprintln!("skipped {}: {error}", path.display());
+            }
+            root
+        }
+        None => {
+            let file = File::open("inputs/day-07.txt").expect("missing input file");
+            let lines = BufReader::new(file)
+                .lines()
+                .map(|res| res.expect("should not fail reading line"));
+            Entry::from_iter(lines)
+        }
+    };
+    match &command.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => solve(&root, &config),
+        ["tree"] => print!("{}", tree(&root, "")),
+        ["transcript"] => print!("{}", transcript(&root)),
+        ["largest", n] => {
+            let n = n.parse::<usize>().expect("should be a number");
+            for (path, size) in largest_dirs(&root, n) {
+                println!("{size}\t{path}");
+            }
+        }
+        ["find", pattern] => {
+            for (path, size) in find_files(&root, pattern) {
+                println!("{size}\t{path}");
+            }
+        }
+        ["freed", path] => {
+            let freed = root
+                .get(path)
+                .unwrap_or_else(|| panic!("no such path {path}"))
+                .size();
+            let free = config.total_size.saturating_sub(root.size()) + freed;
+            println!(
+                "Deleting {path} frees {freed}, leaving {free} free ({} needed)",
+                config.space_needed
+            );
+        }
+        _ => panic!(
+            "usage: day-07 [--disk <path>] [--limit <size>] [--total <size>] [--needed <size>] \
+             [tree | transcript | largest <n> | find <glob> | freed <path>]"
+        ),
+    }
+}
+
+struct Config {
+    dir_size_limit: usize,
+    total_size: usize,
+    space_needed: usize,
+}
+
+fn solve(root: &Entry, config: &Config) {
+    let (disk_used, dir_sizes) = dir_sizes(root, "");
+    let need_to_free = config
+        .space_needed
+        .saturating_sub(config.total_size.saturating_sub(disk_used));
     println!(
         "Part 1: {}",
         dir_sizes
             .iter()
-            .filter(|size| **size <= DIR_SIZE_LIMIT)
+            .map(|(_, size)| size)
+            .filter(|size| **size <= config.dir_size_limit)
             .sum::<usize>()
     );
-    println!("Part 2: {}", dir_sizes
+    if need_to_free == 0 {
+        println!("Part 2: there is enough space already");
+        return;
+    }
+    match dir_sizes
         .iter()
-        .filter(|size| **size >= need_to_free)
-        .min().expect("there should be a result")
-    )
+        .filter(|(_, size)| *size >= need_to_free)
+        .min_by_key(|(_, size)| size)
+    {
+        Some((path, size)) => println!("Part 2: {size} ({path})"),
+        None => println!("Part 2: can't free {need_to_free} by deleting one directory"),
+    }
 }
 
 #[derive(Debug)]
 enum Entry {
-    Dir(Vec<Entry>),
-    File(usize),
+    Dir(String, Vec<Entry>),
+    File(String, usize),
 }
 
 impl Entry {
-    fn from_iter(feed: &mut impl Iterator<Item = String>) -> Self {
-        let mut entries = vec![];
-        while let Some(line) = feed.next() {
-            match &line.split(' ').collect::<Vec<_>>()[..] {
-                ["$", "cd", ".."] => break,
-                ["$", "cd", _name] => entries.push(Entry::from_iter(feed)),
-                ["$", "ls"] => {}    // skip for now
-                ["dir", _name] => {} // skip for now
-                [raw_size, _name] => entries.push(Entry::File(
+    fn from_iter(feed: impl Iterator<Item = String>) -> Self {
+        let mut root = Entry::Dir("/".to_string(), vec![]);
+        // names of the directories from the root down to the current one.
+        let mut cwd: Vec<String> = vec![];
+        for line in feed {
+            // names may have spaces, so only split off as many parts as expected.
+            let parts = if line.starts_with('$') { 3 } else { 2 };
+            match &line.splitn(parts, ' ').collect::<Vec<_>>()[..] {
+                ["$", "cd", "/"] => cwd.clear(),
+                ["$", "cd", ".."] => {
+                    cwd.pop();
+                }
+                ["$", "cd", name] => {
+                    root.dir_mut(&cwd).add(Entry::Dir(name.to_string(), vec![]));
+                    cwd.push(name.to_string());
+                }
+                ["$", "ls"] => {}
+                ["dir", name] => root.dir_mut(&cwd).add(Entry::Dir(name.to_string(), vec![])),
+                [raw_size, name] => root.dir_mut(&cwd).add(Entry::File(
+                    name.to_string(),
                     raw_size.parse::<usize>().expect("should be a size"),
                 )),
                 e => panic!("unexpected entry {e:?}"),
             }
         }
-        Entry::Dir(entries)
+        root
+    }
+
+    // Walks a directory on disk, skipping symlinks so it can't loop. The root
+    // is named "/" so paths read the same as in a transcript. Anything below
+    // it that can't be read, like a directory without permission, is left out
+    // and added to `skipped` along with the reason.
+    fn from_dir(path: &Path, skipped: &mut Vec<(PathBuf, io::Error)>) -> io::Result<Self> {
+        let mut entries = vec![];
+        for dir_entry in fs::read_dir(path)? {
+            let dir_entry = match dir_entry {
+                Ok(dir_entry) => dir_entry,
+                Err(e) => {
+                    skipped.push((path.to_path_buf(), e));
+                    continue;
+                }
+            };
+            let name = dir_entry.file_name().to_string_lossy().to_string();
+            let entry = dir_entry.file_type().and_then(|file_type| {
+                if file_type.is_dir() {
+                    match Entry::from_dir(&dir_entry.path(), skipped)? {
+                        Entry::Dir(_, sub_entries) => Ok(Some(Entry::Dir(name, sub_entries))),
+                        Entry::File(..) => unreachable!("walking a directory gives a directory"),
+                    }
+                } else if file_type.is_file() {
+                    Ok(Some(Entry::File(
+                        name,
+                        dir_entry.metadata()?.len() as usize,
+                    )))
+                } else {
+                    Ok(None)
+                }
+            });
+            match entry {
+                Ok(entry) => entries.extend(entry),
+                Err(e) => skipped.push((dir_entry.path(), e)),
+            }
+        }
+        entries.sort_by(|e1, e2| e1.name().cmp(e2.name()));
+        Ok(Entry::Dir("/".to_string(), entries))
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Entry::Dir(name, _) | Entry::File(name, _) => name,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Entry::Dir(_, entries) => entries.iter().map(Entry::size).sum(),
+            Entry::File(_, size) => *size,
+        }
+    }
+
+    fn get(&self, path: &str) -> Option<&Self> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |entry, name| match entry {
+                Entry::Dir(_, entries) => entries.iter().find(|e| e.name() == name),
+                Entry::File(..) => None,
+            })
+    }
+
+    fn dir_mut(&mut self, path: &[String]) -> &mut Self {
+        match path {
+            [] => self,
+            [name, rest @ ..] => match self {
+                Entry::Dir(_, entries) => entries
+                    .iter_mut()
+                    .find(|e| matches!(e, Entry::Dir(..)) && e.name() == name)
+                    .expect("should have listed the directory")
+                    .dir_mut(rest),
+                Entry::File(file, _) => panic!("{file} is not a directory"),
+            },
+        }
+    }
+
+    // Listing a directory again, or going back into it, finds entries that
+    // are already known, so those are kept as they are.
+    fn add(&mut self, entry: Entry) {
+        match self {
+            Entry::Dir(_, entries) => {
+                if !entries.iter().any(|e| e.name() == entry.name()) {
+                    entries.push(entry)
+                }
+            }
+            Entry::File(file, _) => panic!("{file} is not a directory"),
+        }
+    }
+}
+
+fn join_path(parent: &str, name: &str) -> String {
+    match (parent, name) {
+        ("", name) => name.to_string(),
+        ("/", name) => format!("/{name}"),
+        (parent, name) => format!("{parent}/{name}"),
     }
 }
 
-fn dir_sizes(entries: &[Entry]) -> (usize, Vec<usize>) {
-    let mut sizes = vec![];
-    let mut current_size = 0;
-    for entry in entries {
-        match entry {
-            Entry::File(size) => current_size += size,
-            Entry::Dir(entries) => {
-                let (sub_size, breakdown) = dir_sizes(entries);
+// Returns the size of the entry, and the full path and size of every
+// directory in it, including itself.
+fn dir_sizes(entry: &Entry, parent: &str) -> (usize, Vec<(String, usize)>) {
+    match entry {
+        Entry::File(_, size) => (*size, vec![]),
+        Entry::Dir(name, entries) => {
+            let path = join_path(parent, name);
+            let mut sizes = vec![];
+            let mut current_size = 0;
+            for entry in entries {
+                let (sub_size, breakdown) = dir_sizes(entry, &path);
                 current_size += sub_size;
                 sizes.extend(breakdown)
             }
+            sizes.push((path, current_size));
+            (current_size, sizes)
+        }
+    }
+}
+
+// Renders the entry like the listing in the puzzle, with cumulative sizes.
+fn tree(entry: &Entry, indent: &str) -> String {
+    match entry {
+        Entry::File(name, size) => format!("{indent}- {name} (file, size={size})\n"),
+        Entry::Dir(name, entries) => {
+            let mut output = format!("{indent}- {name} (dir, size={})\n", entry.size());
+            let indent = format!("{indent}  ");
+            for entry in entries {
+                output += &tree(entry, &indent);
+            }
+            output
+        }
+    }
+}
+
+fn largest_dirs(root: &Entry, n: usize) -> Vec<(String, usize)> {
+    let mut sizes = dir_sizes(root, "").1;
+    sizes.sort_by(|(p1, s1), (p2, s2)| s2.cmp(s1).then(p1.cmp(p2)));
+    sizes.truncate(n);
+    sizes
+}
+
+fn files(entry: &Entry, parent: &str) -> Vec<(String, usize)> {
+    let path = join_path(parent, entry.name());
+    match entry {
+        Entry::File(_, size) => vec![(path, *size)],
+        Entry::Dir(_, entries) => entries.iter().flat_map(|e| files(e, &path)).collect(),
+    }
+}
+
+// Patterns with a '/' are matched against the full path, others against the
+// file name only.
+fn find_files(root: &Entry, pattern: &str) -> Vec<(String, usize)> {
+    files(root, "")
+        .into_iter()
+        .filter(|(path, _)| {
+            let target = if pattern.contains('/') {
+                path
+            } else {
+                path.rsplit('/').next().unwrap_or(path)
+            };
+            glob_match(
+                &pattern.chars().collect::<Vec<_>>(),
+                &target.chars().collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+// Supports `*` for any run of characters and `?` for any single one.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match (pattern, text) {
+        ([], []) => true,
+        (['*', rest @ ..], _) => {
+            glob_match(rest, text) || (!text.is_empty() && glob_match(pattern, &text[1..]))
+        }
+        (['?', p_rest @ ..], [_, t_rest @ ..]) => glob_match(p_rest, t_rest),
+        ([p, p_rest @ ..], [t, t_rest @ ..]) if p == t => glob_match(p_rest, t_rest),
+        _ => false,
+    }
+}
+
+// Produces the `cd`/`ls` session that would have discovered the entry.
+fn transcript(entry: &Entry) -> String {
+    match entry {
+        Entry::File(..) => String::new(),
+        Entry::Dir(name, entries) => {
+            let mut output = format!("$ cd {name}\n$ ls\n");
+            for entry in entries {
+                output += &match entry {
+                    Entry::Dir(name, _) => format!("dir {name}\n"),
+                    Entry::File(name, size) => format!("{size} {name}\n"),
+                };
+            }
+            for entry in entries.iter().filter(|e| matches!(e, Entry::Dir(..))) {
+                output += &transcript(entry);
+                output += "$ cd ..\n";
+            }
+            output
         }
     }
-    sizes.push(current_size);
-    (current_size, sizes)
 }
@@ -1,26 +1,135 @@
-use std::fs::read_to_string;
+use std::{
+    env,
+    fs::{read_to_string, write},
+    time::Instant,
+};
+
+// Above this size the walking approach takes too long to compare against.
+const BENCH_WALK_LIMIT: usize = 500;
 
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let Some("bench") = args.first().map(String::as_str) {
+        let size = args
+            .get(1)
+            .map(|n| n.parse::<usize>().expect("size should be a number"))
+            .unwrap_or(5000);
+        bench(size);
+        return;
+    }
     let input = read_to_string("inputs/day-08.txt").expect("missing input file");
-    let trees = to_trees(parse_input(input));
-    println!("Part 1: {}", count_visible(&trees));
-    println!("Part 2: {}", max_scenic_score(&trees));
+    let grid = parse_input(input);
+    match &args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => {
+            let survey = scan_trees(&grid, &OFFSETS);
+            println!("Part 1: {}", count_visible(&survey));
+            println!("Part 2: {}", max_scenic_score(&survey));
+        }
+        ["diagonal"] => {
+            let survey = scan_trees(&grid, &ALL_OFFSETS);
+            println!("Visible from outside: {}", count_visible(&survey));
+            println!("Max scenic score: {}", max_scenic_score(&survey));
+        }
+        ["from", x, y, height] => {
+            let parse = |n: &str| n.parse::<usize>().expect("should be a number");
+            let (x, y, height) = (parse(x), parse(y), parse(height) as u32);
+            let (rows, cols) = (grid.len(), grid.first().map_or(0, Vec::len));
+            if x >= rows || y >= cols {
+                panic!(
+                    "usage: day-08 from <x> <y> <height>, with x below {rows} and y below {cols}"
+                );
+            }
+            let visible = visible_from(&grid, (x, y), height);
+            println!("{} trees visible from ({x}, {y})", visible.len());
+            println!("{}", render_visible(&grid, (x, y), &visible));
+        }
+        ["export", prefix] => {
+            let survey = scan_trees(&grid, &OFFSETS);
+            let cols = grid.first().map_or(0, Vec::len);
+            let visible = survey
+                .visible
+                .iter()
+                .map(|v| *v as usize)
+                .collect::<Vec<_>>();
+            write(format!("{prefix}-scores.csv"), to_csv(&survey.scores, cols))
+                .expect("should write scores");
+            write(format!("{prefix}-visible.csv"), to_csv(&visible, cols))
+                .expect("should write visibility");
+            write(format!("{prefix}-scores.pgm"), to_pgm(&survey.scores, cols))
+                .expect("should write heatmap");
+            let (x, y) = best_spot(&survey, cols).expect("there should be a tree");
+            println!(
+                "Max scenic score {} at ({x}, {y})",
+                survey.scores[x * cols + y]
+            );
+        }
+        _ => panic!(
+            "usage: day-08 [bench <size> | diagonal | from <x> <y> <height> | export <prefix>]"
+        ),
+    }
+}
+
+fn bench(size: usize) {
+    let grid = generate_forest(size);
+    let start = Instant::now();
+    let survey = scan_trees(&grid, &OFFSETS);
+    let (visible, score) = (count_visible(&survey), max_scenic_score(&survey));
+    println!("{size}x{size} scanned in {:?}", start.elapsed());
+    println!("Visible: {visible}, max scenic score: {score}");
+    if size <= BENCH_WALK_LIMIT {
+        let start = Instant::now();
+        let walked = Survey::from_trees(&to_trees(&grid, &OFFSETS));
+        println!("{size}x{size} walked in {:?}", start.elapsed());
+        assert!(
+            survey == walked,
+            "scanning should agree with walking to the edges"
+        );
+    }
+}
+
+// Pseudo-random heights from a fixed seed, so runs can be compared.
+fn generate_forest(size: usize) -> Grid {
+    let mut seed: u64 = 0x2022_0008;
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| {
+                    seed = seed
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1_442_695_040_888_963_407);
+                    ((seed >> 33) % 10) as u32
+                })
+                .collect()
+        })
+        .collect()
 }
 
 const OFFSETS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, 1), (0, -1)];
+// the cardinal directions followed by the diagonals, NW NE SW SE.
+const ALL_OFFSETS: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, 1),
+    (0, -1),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
 
-#[derive(Debug)]
-struct Tree {
-    // directions in order NSEW. Shows if we are blocked, and how far ahead.
-    blockers: [(bool, usize); 4],
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Tree<const N: usize> {
+    // one per direction, in the order of the offsets used to find them. Shows
+    // if we are blocked, and how far ahead.
+    blockers: [(bool, usize); N],
 }
 
 type Grid = Vec<Vec<u32>>;
 
-impl Tree {
-    fn from(grid: &Grid, x: usize, y: usize) -> Self {
-        let mut blockers = [(false, 0); 4];
-        for (i, offset) in OFFSETS.iter().enumerate() {
+impl<const N: usize> Tree<N> {
+    fn from(grid: &Grid, x: usize, y: usize, offsets: &[(i32, i32); N]) -> Self {
+        let mut blockers = [(false, 0); N];
+        for (i, offset) in offsets.iter().enumerate() {
             blockers[i] = find_blocker(grid, x, y, offset);
         }
         Self { blockers }
@@ -35,6 +144,24 @@ impl Tree {
     }
 }
 
+// Whether each tree is visible from outside, and its scenic score, in reading
+// order. Kept apart from `Tree` as that holds every direction for every tree,
+// which is too much memory for large forests.
+#[derive(Debug, PartialEq, Eq)]
+struct Survey {
+    visible: Vec<bool>,
+    scores: Vec<usize>,
+}
+
+impl Survey {
+    fn from_trees<const N: usize>(trees: &[Tree<N>]) -> Self {
+        Self {
+            visible: trees.iter().map(Tree::is_visible).collect(),
+            scores: trees.iter().map(Tree::scenic_score).collect(),
+        }
+    }
+}
+
 fn parse_input(input: String) -> Vec<Vec<u32>> {
     input
         .lines()
@@ -46,16 +173,87 @@ fn parse_input(input: String) -> Vec<Vec<u32>> {
         .collect()
 }
 
-fn to_trees(grid: Vec<Vec<u32>>) -> Vec<Tree> {
+fn to_trees<const N: usize>(grid: &Grid, offsets: &[(i32, i32); N]) -> Vec<Tree<N>> {
     (0..grid.len())
         .flat_map(|x| {
             (0..grid[x].len())
-                .map(|y| Tree::from(&grid, x, y))
+                .map(|y| Tree::from(grid, x, y, offsets))
                 .collect::<Vec<_>>()
         })
         .collect()
 }
 
+// Finds the closest tree before the `i`th one of a line that is at least as
+// tall. The stack holds the trees seen so far that could still block later
+// ones, and is shared along the line.
+fn look_back(stack: &mut Vec<(usize, u32)>, i: usize, height: u32) -> (bool, usize) {
+    while matches!(stack.last(), Some((_, h)) if *h < height) {
+        stack.pop();
+    }
+    let blocker = match stack.last() {
+        Some((j, _)) => (true, i - j),
+        None => (false, i),
+    };
+    stack.push((i, height));
+    blocker
+}
+
+// The cells at the edge the offset leads out of, where lines running that way
+// end.
+fn edge_cells(rows: usize, cols: usize, (dx, dy): (i32, i32)) -> Vec<(usize, usize)> {
+    let edge_row = match dx {
+        1 => Some(rows - 1),
+        -1 => Some(0),
+        _ => None,
+    };
+    let edge_col = match dy {
+        1 => Some(cols - 1),
+        -1 => Some(0),
+        _ => None,
+    };
+    let mut cells = vec![];
+    if let Some(x) = edge_row {
+        cells.extend((0..cols).map(|y| (x, y)));
+    }
+    if let Some(y) = edge_col {
+        cells.extend((0..rows).filter(|x| Some(*x) != edge_row).map(|x| (x, y)));
+    }
+    cells
+}
+
+// Same results as `to_trees`, but in O(n²). For each direction, every line of
+// trees running that way is walked from the far edge back, looking back for
+// blockers, instead of walking to the edge from every tree.
+fn scan_trees(grid: &Grid, offsets: &[(i32, i32)]) -> Survey {
+    let rows = grid.len();
+    let cols = grid.first().map_or(0, Vec::len);
+    let mut survey = Survey {
+        visible: vec![false; rows * cols],
+        scores: vec![1; rows * cols],
+    };
+    if rows == 0 || cols == 0 {
+        return survey;
+    }
+    let mut stack = vec![];
+    for (dx, dy) in offsets {
+        for (ex, ey) in edge_cells(rows, cols, (*dx, *dy)) {
+            stack.clear();
+            let (mut x, mut y) = (ex as i32, ey as i32);
+            for i in 0.. {
+                if x < 0 || y < 0 || x as usize >= rows || y as usize >= cols {
+                    break;
+                }
+                let (blocked, distance) = look_back(&mut stack, i, grid[x as usize][y as usize]);
+                let cell = x as usize * cols + y as usize;
+                survey.visible[cell] |= !blocked;
+                survey.scores[cell] *= distance;
+                (x, y) = (x - dx, y - dy);
+            }
+        }
+    }
+    survey
+}
+
 fn find_blocker(grid: &Grid, x: usize, y: usize, (dx, dy): &(i32, i32)) -> (bool, usize) {
     let (mut nx, mut ny) = (x as i32, y as i32);
     let height = grid[x][y];
@@ -76,14 +274,146 @@ fn is_out_of_bounds(grid: &Grid, x: i32, y: i32) -> bool {
     x < 0 || x == grid.len() as i32 || y < 0 || y == grid[x as usize].len() as i32
 }
 
-fn count_visible(trees: &[Tree]) -> usize {
-    trees.iter().filter(|t| t.is_visible()).count()
+fn count_visible(survey: &Survey) -> usize {
+    survey.visible.iter().filter(|v| **v).count()
 }
 
-fn max_scenic_score(trees: &[Tree]) -> usize {
-    trees
+fn max_scenic_score(survey: &Survey) -> usize {
+    survey
+        .scores
         .iter()
-        .map(|t| t.scenic_score())
+        .cloned()
         .max()
         .expect("there should be a max")
 }
+
+// Finds the trees seen from a viewpoint at the given height. The sight line
+// to each tree runs from the viewpoint to its top, stepping through the cells
+// in between; any of those trees reaching the line blocks the view.
+fn visible_from(grid: &Grid, (x, y): (usize, usize), height: u32) -> Vec<(usize, usize)> {
+    let mut visible = vec![];
+    for (tx, row) in grid.iter().enumerate() {
+        for (ty, target) in row.iter().enumerate() {
+            if (tx, ty) == (x, y) {
+                continue;
+            }
+            let (dx, dy) = (tx as i64 - x as i64, ty as i64 - y as i64);
+            let steps = dx.abs().max(dy.abs());
+            let rise = *target as i64 - height as i64;
+            let blocked = (1..steps).any(|k| {
+                let cx = x as i64 + (dx * k + steps / 2).div_euclid(steps);
+                let cy = y as i64 + (dy * k + steps / 2).div_euclid(steps);
+                let h = grid[cx as usize][cy as usize] as i64;
+                // h >= height + rise * k / steps, without the division.
+                h * steps >= height as i64 * steps + rise * k
+            });
+            if !blocked {
+                visible.push((tx, ty));
+            }
+        }
+    }
+    visible
+}
+
+fn render_visible(grid: &Grid, viewpoint: (usize, usize), visible: &[(usize, usize)]) -> String {
+    grid.iter()
+        .enumerate()
+        .map(|(x, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(y, h)| match (x, y) {
+                    p if p == viewpoint => '*',
+                    p if visible.contains(&p) => char::from_digit(*h, 10).unwrap_or('?'),
+                    _ => '.',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Coordinates of the tree with the highest scenic score, the first one in
+// reading order if there is a tie.
+fn best_spot(survey: &Survey, cols: usize) -> Option<(usize, usize)> {
+    let (i, _) = survey
+        .scores
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, score)| **score)?;
+    Some((i / cols, i % cols))
+}
+
+fn to_csv(values: &[usize], cols: usize) -> String {
+    values
+        .chunks(cols)
+        .map(|row| {
+            row.iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+                + "\n"
+        })
+        .collect()
+}
+
+// Plain grayscale PGM, scaled so the highest value is white.
+fn to_pgm(values: &[usize], cols: usize) -> String {
+    let max = values.iter().max().cloned().unwrap_or(0).max(1);
+    let rows = values.len() / cols.max(1);
+    let mut output = format!("P2\n{cols} {rows}\n255\n");
+    for row in values.chunks(cols) {
+        output += &row
+            .iter()
+            .map(|v| (v * 255 / max).to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        output += "\n";
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "30373\n25512\n65332\n33549\n35390";
+
+    #[test]
+    fn scan_matches_walk_on_sample() {
+        let grid = parse_input(SAMPLE.to_string());
+        let survey = scan_trees(&grid, &OFFSETS);
+        assert_eq!(survey, Survey::from_trees(&to_trees(&grid, &OFFSETS)));
+        assert_eq!(count_visible(&survey), 21);
+        assert_eq!(max_scenic_score(&survey), 8);
+    }
+
+    #[test]
+    fn scan_matches_walk_on_generated_forests() {
+        for size in [1, 2, 7, 50] {
+            let grid = generate_forest(size);
+            assert_eq!(
+                scan_trees(&grid, &OFFSETS),
+                Survey::from_trees(&to_trees(&grid, &OFFSETS)),
+                "size {size}"
+            );
+        }
+    }
+
+    #[test]
+    fn diagonal_scan_matches_walk() {
+        let grid = parse_input(SAMPLE.to_string());
+        assert_eq!(
+            scan_trees(&grid, &ALL_OFFSETS),
+            Survey::from_trees(&to_trees(&grid, &ALL_OFFSETS))
+        );
+        for size in [1, 2, 7, 50] {
+            let grid = generate_forest(size);
+            assert_eq!(
+                scan_trees(&grid, &ALL_OFFSETS),
+                Survey::from_trees(&to_trees(&grid, &ALL_OFFSETS)),
+                "size {size}"
+            );
+        }
+    }
+}
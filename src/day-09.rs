@@ -1,54 +1,151 @@
 use std::{
     collections::HashSet,
+    env,
     fs::File,
     io::{BufRead, BufReader},
 };
 
+const DEFAULT_KNOTS: usize = 10;
+
 fn main() {
+    let mut knots = None;
+    let mut render = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--knots" => {
+                knots = args.next().and_then(|n| n.parse::<usize>().ok());
+                assert!(knots.is_some(), "--knots should be followed by a number");
+            }
+            "--render" => render = true,
+            _ => panic!("usage: day-09 [--knots <n>] [--render]"),
+        }
+    }
+    let length = knots.unwrap_or(DEFAULT_KNOTS);
     let file = File::open("inputs/day-09.txt").expect("missing input file");
     let lines = BufReader::new(file)
         .lines()
         .map(|res| res.expect("should not fail reading line"));
-    let mut trail = get_head_trail(lines);
-    trail = get_follower_trail(trail);
-    println!("Part 1: {}", trail.iter().collect::<HashSet<_>>().len());
-    for _ in 0..8 {
-        trail = get_follower_trail(trail);
+    let mut rope = Rope::new(length);
+    if render {
+        let moves = lines.collect::<Vec<_>>();
+        let bounds = head_bounds(&moves);
+        for line in moves {
+            let (offset, amount) = parse_move(&line);
+            for _ in 0..amount {
+                rope.step(offset);
+            }
+            println!("== {line} ==\n\n{}\n", rope.render(bounds));
+        }
+    } else {
+        for line in lines {
+            let (offset, amount) = parse_move(&line);
+            for _ in 0..amount {
+                rope.step(offset);
+            }
+        }
+    }
+    match knots {
+        None => {
+            println!("Part 1: {}", rope.visited[1].len());
+            println!("Part 2: {}", rope.visited[9].len());
+        }
+        Some(_) => {
+            for (i, visited) in rope.visited.iter().enumerate() {
+                println!("Knot {i}: {}", visited.len());
+            }
+        }
+    }
+}
+
+struct Rope {
+    // head first.
+    knots: Vec<(i32, i32)>,
+    // positions each knot has been in, in the same order as the knots.
+    visited: Vec<HashSet<(i32, i32)>>,
+}
+
+impl Rope {
+    fn new(length: usize) -> Self {
+        assert!(length > 0, "a rope needs at least one knot");
+        Self {
+            knots: vec![(0, 0); length],
+            visited: vec![HashSet::from([(0, 0)]); length],
+        }
+    }
+
+    // Moves the head by one step, and every knot after it follows the one before.
+    fn step(&mut self, (dx, dy): (i32, i32)) {
+        let (hx, hy) = self.knots[0];
+        self.knots[0] = (hx + dx, hy + dy);
+        self.visited[0].insert(self.knots[0]);
+        for i in 1..self.knots.len() {
+            let (lx, ly) = self.knots[i - 1];
+            let (tx, ty) = self.knots[i];
+            let (dx, dy) = (lx - tx, ly - ty);
+            if dx.abs() <= 1 && dy.abs() <= 1 {
+                // the rest of the rope can't move either.
+                break;
+            }
+            self.knots[i] = (tx + num::signum(dx), ty + num::signum(dy));
+            self.visited[i].insert(self.knots[i]);
+        }
+    }
+
+    // Draws the rope like the puzzle does: the head as H, then the knots by
+    // number (letters after 9), the start as s and cells the tail visited as #.
+    fn render(&self, ((min_x, min_y), (max_x, max_y)): Bounds) -> String {
+        let tail = &self.visited[self.visited.len() - 1];
+        (min_y..=max_y)
+            .rev()
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| match self.knots.iter().position(|k| *k == (x, y)) {
+                        Some(0) => 'H',
+                        Some(i) => char::from_digit(i as u32 % 36, 36).unwrap_or('?'),
+                        None if (x, y) == (0, 0) => 's',
+                        None if tail.contains(&(x, y)) => '#',
+                        None => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
-    println!("Part 2: {}", trail.iter().collect::<HashSet<_>>().len());
 }
 
-fn get_head_trail(directions: impl Iterator<Item = String>) -> Vec<(i32, i32)> {
-    let mut trail = vec![(0, 0)];
-    for direction in directions {
-        let mut parts = direction.split(' ');
-        let dir = parts.next().expect("should have a part");
-        let amount = parts
-            .next()
-            .expect("should have a second part")
-            .parse::<i32>()
-            .expect("should be a number");
-        let (dx, dy) = offset_for_direction(dir);
+// min and max of x and y, as (min, max) pairs.
+type Bounds = ((i32, i32), (i32, i32));
+
+// Knots never leave the area the head has been in, so that area fits them all.
+fn head_bounds(moves: &[String]) -> Bounds {
+    let mut head = Rope::new(1);
+    for line in moves {
+        let (offset, amount) = parse_move(line);
         for _ in 0..amount {
-            let (lx, ly) = trail.last().expect("should have a position");
-            trail.push((lx + dx, ly + dy));
+            head.step(offset);
         }
     }
-    trail
+    head.visited[0].iter().fold(
+        ((0, 0), (0, 0)),
+        |((min_x, min_y), (max_x, max_y)), (x, y)| {
+            (
+                (min_x.min(*x), min_y.min(*y)),
+                (max_x.max(*x), max_y.max(*y)),
+            )
+        },
+    )
 }
 
-fn get_follower_trail(head: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
-    head.into_iter().fold(vec![], |mut trail, (hx, hy)| {
-        let (tx, ty) = trail.last().unwrap_or(&(0, 0));
-        let (dx, dy) = (hx - tx, hy - ty);
-        let (mx, my) = if dx.abs() > 1 || dy.abs() > 1 {
-            (num::signum(dx), num::signum(dy))
-        } else {
-            (0, 0)
-        };
-        trail.push((tx + mx, ty + my));
-        trail
-    })
+fn parse_move(line: &str) -> ((i32, i32), i32) {
+    let mut parts = line.split(' ');
+    let dir = parts.next().expect("should have a part");
+    let amount = parts
+        .next()
+        .expect("should have a second part")
+        .parse::<i32>()
+        .expect("should be a number");
+    (offset_for_direction(dir), amount)
 }
 
 fn offset_for_direction(direction: &str) -> (i32, i32) {
@@ -57,6 +154,10 @@ fn offset_for_direction(direction: &str) -> (i32, i32) {
         "D" => (0, -1),
         "L" => (-1, 0),
         "R" => (1, 0),
+        "UL" => (-1, 1),
+        "UR" => (1, 1),
+        "DL" => (-1, -1),
+        "DR" => (1, -1),
         err => panic!("what sort of direction is {err}"),
     }
 }
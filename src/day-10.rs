@@ -1,92 +1,448 @@
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    env,
+    fmt::{self, Display},
+    fs::read_to_string,
+    io::{self, BufRead, Write},
 };
 
+const MAX_CYCLES: usize = 10_000;
+const DATA_POINTS: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
 fn main() {
-    let file = File::open("inputs/day-10.txt").expect("missing input file");
-    let operations = BufReader::new(file).lines().map(|res| {
-        res.map(Operation::from_line)
-            .expect("should not fail reading line")
-    });
-    let mut cpu = Cpu::new(operations);
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match &args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => solve(&DATA_POINTS),
+        ["--samples", samples] => solve(
+            &samples
+                .split(',')
+                .map(|s| s.parse::<usize>().expect("samples should be numbers"))
+                .collect::<Vec<_>>(),
+        ),
+        ["trace", format] => trace(&load("inputs/day-10.txt"), format),
+        ["trace", format, path] => trace(&load(path), format),
+        ["run", path] => run(&load(path), MAX_CYCLES),
+        ["run", path, max_cycles] => run(
+            &load(path),
+            max_cycles.parse::<usize>().expect("should be a number"),
+        ),
+        ["disasm", path] => print!("{}", disassemble(&load(path))),
+        ["debug"] => Debugger::new(load("inputs/day-10.txt")).repl(),
+        ["debug", path] => Debugger::new(load(path)).repl(),
+        _ => panic!(
+            "usage: day-10 [--samples <cycles> | run <file> [max cycles] | disasm <file> | \
+             debug [file] | trace <csv|json> [file]]"
+        ),
+    }
+}
+
+fn load(path: &str) -> Vec<Operation> {
+    let source = read_to_string(path).expect("missing program file");
+    assemble(&source).unwrap_or_else(|err| panic!("{path}: {err}"))
+}
+
+// Runs a program until it goes past its last instruction.
+fn run(program: &[Operation], max_cycles: usize) {
+    let mut cpu = Cpu::new(program.to_vec());
+    while !cpu.halted() && cpu.clock < max_cycles {
+        cpu.tick();
+    }
+    let registers = REGISTERS
+        .iter()
+        .map(|r| format!("{r}={}", cpu.registers[r.index()]))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if cpu.halted() {
+        println!("Halted after {} cycles: {registers}", cpu.clock);
+    } else {
+        println!("Still running after {} cycles: {registers}", cpu.clock);
+    }
+}
+
+// Prints the state during every cycle, for as long as the CRT draws and the
+// program runs. The pixel is left empty once past the CRT.
+fn trace(program: &[Operation], format: &str) {
+    let mut cpu = Cpu::new(program.to_vec());
     let mut crt = Crt::new();
-    let data_points = [20, 60, 100, 140, 180, 220];
+    if format == "csv" {
+        let registers = REGISTERS.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        println!("clock,{},operation,address,pixel", registers.join(","));
+    }
+    while (cpu.clock < WIDTH * HEIGHT || !cpu.halted()) && cpu.clock < MAX_CYCLES {
+        cpu.tick();
+        let pixel = Crt::can_draw(cpu.clock).then(|| crt.draw(cpu.clock, cpu.register()));
+        let in_flight = cpu
+            .operation
+            .map(|(op, address, _)| (op.to_string(), address));
+        match format {
+            "csv" => println!(
+                "{},{},{},{}",
+                cpu.clock,
+                cpu.registers.map(|r| r.to_string()).join(","),
+                in_flight.map_or(",".to_string(), |(op, address)| format!("{op},{address}")),
+                pixel.map_or(String::new(), String::from)
+            ),
+            "json" => {
+                let registers = REGISTERS
+                    .iter()
+                    .map(|r| format!("\"{r}\":{}", cpu.registers[r.index()]))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let (operation, address) = match in_flight {
+                    Some((op, address)) => (format!("\"{op}\""), address.to_string()),
+                    None => ("null".to_string(), "null".to_string()),
+                };
+                let pixel = pixel.map_or("null".to_string(), |p| format!("\"{p}\""));
+                println!(
+                    "{{\"clock\":{},{registers},\"operation\":{operation},\"address\":{address},\"pixel\":{pixel}}}",
+                    cpu.clock
+                )
+            }
+            _ => panic!("unknown trace format {format}, expected csv or json"),
+        }
+    }
+}
+
+fn solve(data_points: &[usize]) {
+    let program = load("inputs/day-10.txt");
+    let mut cpu = Cpu::new(program);
+    let mut crt = Crt::new();
+    let last_cycle = data_points.iter().cloned().fold(WIDTH * HEIGHT, usize::max);
     let mut sig_sum = 0;
-    for _ in 0..240 {
+    for _ in 0..last_cycle {
         cpu.tick();
-        crt.draw(cpu.clock, cpu.register);
+        if Crt::can_draw(cpu.clock) {
+            crt.draw(cpu.clock, cpu.register());
+        }
         if data_points.contains(&cpu.clock) {
-            sig_sum += cpu.clock as i32 * cpu.register;
+            sig_sum += cpu.clock as i32 * cpu.register();
         }
     }
     println!("Part 1: {}", sig_sum);
-    println!("Part 2: {}", crt.output());
+    match crt.read() {
+        Ok(letters) => println!("Part 2: {letters}"),
+        Err(err) => println!("Part 2: {err}{}", crt.output()),
+    }
 }
 
 const HEIGHT: usize = 6;
 const WIDTH: usize = 40;
+const GLYPH_WIDTH: usize = 4;
+// glyphs are separated by a blank column.
+const GLYPH_SPACING: usize = GLYPH_WIDTH + 1;
+
+// The letters known to show up on the CRT, drawn the way it draws them.
+#[rustfmt::skip]
+const FONT: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug)]
+struct UnknownGlyphs {
+    // the first display column of each glyph that isn't in the font.
+    columns: Vec<usize>,
+}
+
+impl Display for UnknownGlyphs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "unrecognised glyphs at columns {columns}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Register {
+    X,
+    Y,
+    Z,
+}
+
+const REGISTERS: [Register; 3] = [Register::X, Register::Y, Register::Z];
+
+impl Register {
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "x" => Ok(Self::X),
+            "y" => Ok(Self::Y),
+            "z" => Ok(Self::Z),
+            _ => Err(format!("unknown register '{name}'")),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::X => write!(f, "x"),
+            Self::Y => write!(f, "y"),
+            Self::Z => write!(f, "z"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operand {
+    Value(i32),
+    Register(Register),
+}
 
-#[derive(Clone, Copy, Debug)]
+impl Operand {
+    fn parse(text: &str) -> Result<Self, String> {
+        match text.parse::<i32>() {
+            Ok(value) => Ok(Self::Value(value)),
+            Err(_) => Register::from_name(text).map(Self::Register),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Value(value) => write!(f, "{value}"),
+            Self::Register(register) => write!(f, "{register}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Condition {
+    Zero,
+    NonZero,
+    Positive,
+    Negative,
+}
+
+const CONDITIONS: [(Condition, &str); 4] = [
+    (Condition::Zero, "jz"),
+    (Condition::NonZero, "jnz"),
+    (Condition::Positive, "jgz"),
+    (Condition::Negative, "jlz"),
+];
+
+impl Condition {
+    fn holds(&self, value: i32) -> bool {
+        match self {
+            Self::Zero => value == 0,
+            Self::NonZero => value != 0,
+            Self::Positive => value > 0,
+            Self::Negative => value < 0,
+        }
+    }
+}
+
+// Arithmetic is written with the register as a suffix, like the puzzle's
+// `addx`: `add<r> <operand>`, `mul<r> <operand>` and `set<r> <operand>`.
+// Jumps are relative to the jumping instruction: `jmp <offset>`, and
+// `jz`, `jnz`, `jgz` or `jlz` followed by `<register> <offset>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Operation {
-    AddX(i32),
     Noop,
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Set(Register, Operand),
+    Jump(i32),
+    Branch(Condition, Register, i32),
 }
 
 impl Operation {
-    fn ticks(&self) -> usize {
+    // How many cycles the operation takes, its result showing after the last.
+    fn cycles(&self) -> usize {
         match self {
-            Self::Noop => 0,
-            Self::AddX(_) => 1,
+            Self::Noop | Self::Set(..) => 1,
+            Self::Add(..) | Self::Jump(_) | Self::Branch(..) => 2,
+            Self::Mul(..) => 3,
         }
     }
 
-    fn from_line(line: String) -> Self {
-        match line.split(' ').collect::<Vec<_>>()[..] {
-            ["noop"] => Self::Noop,
-            ["addx", amount] => Self::AddX(amount.parse::<i32>().expect("should be num")),
-            _ => panic!("unrecognized op"),
+    fn parse(text: &str) -> Result<Self, String> {
+        let offset = |text: &str| {
+            text.parse::<i32>()
+                .map_err(|_| format!("'{text}' is not an offset"))
+        };
+        match text.split_whitespace().collect::<Vec<_>>()[..] {
+            ["noop"] => Ok(Self::Noop),
+            ["jmp", to] => Ok(Self::Jump(offset(to)?)),
+            [mnemonic, register, to] => match CONDITIONS.iter().find(|(_, m)| *m == mnemonic) {
+                Some((condition, _)) => Ok(Self::Branch(
+                    *condition,
+                    Register::from_name(register)?,
+                    offset(to)?,
+                )),
+                None => Err(format!("unknown instruction '{mnemonic}'")),
+            },
+            [mnemonic, operand] if mnemonic.len() > 3 && mnemonic.is_char_boundary(3) => {
+                let (name, register) = mnemonic.split_at(3);
+                let register = Register::from_name(register)?;
+                let operand = Operand::parse(operand)?;
+                match name {
+                    "add" => Ok(Self::Add(register, operand)),
+                    "mul" => Ok(Self::Mul(register, operand)),
+                    "set" => Ok(Self::Set(register, operand)),
+                    _ => Err(format!("unknown instruction '{mnemonic}'")),
+                }
+            }
+            _ => Err(format!("can't read instruction '{}'", text.trim())),
+        }
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Noop => write!(f, "noop"),
+            Self::Add(register, operand) => write!(f, "add{register} {operand}"),
+            Self::Mul(register, operand) => write!(f, "mul{register} {operand}"),
+            Self::Set(register, operand) => write!(f, "set{register} {operand}"),
+            Self::Jump(to) => write!(f, "jmp {to}"),
+            Self::Branch(condition, register, to) => {
+                let (_, mnemonic) = CONDITIONS
+                    .iter()
+                    .find(|(c, _)| c == condition)
+                    .expect("every condition has a mnemonic");
+                write!(f, "{mnemonic} {register} {to}")
+            }
         }
     }
 }
 
-struct Cpu<T: Iterator<Item = Operation>> {
+#[derive(Debug)]
+struct AsmError {
+    line: usize,
+    message: String,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// One instruction per line. Blank lines and anything after a `;` are ignored.
+fn assemble(source: &str) -> Result<Vec<Operation>, AsmError> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split(';').next().unwrap_or("")))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line, text)| Operation::parse(text).map_err(|message| AsmError { line, message }))
+        .collect()
+}
+
+// Lists the program with the address of each instruction in a comment, so it
+// can be assembled again.
+fn disassemble(program: &[Operation]) -> String {
+    program
+        .iter()
+        .enumerate()
+        .map(|(address, op)| format!("{:<16}; {address}\n", op.to_string()))
+        .collect()
+}
+
+struct Cpu {
     clock: usize,
-    register: i32,
-    operation: Option<(Operation, usize)>,
-    stream: T,
+    registers: [i32; REGISTERS.len()],
+    // address of the next operation to start.
+    pc: usize,
+    // the operation in flight, its address, and the cycles it has left.
+    operation: Option<(Operation, usize, usize)>,
+    program: Vec<Operation>,
 }
 
-impl<T: Iterator<Item = Operation>> Cpu<T> {
-    fn new(stream: T) -> Self {
+impl Cpu {
+    fn new(program: Vec<Operation>) -> Self {
         Self {
             clock: 0,
-            register: 1,
+            registers: [1, 0, 0],
+            pc: 0,
             operation: None,
-            stream,
+            program,
         }
     }
 
+    // The x register, which holds the sprite position.
+    fn register(&self) -> i32 {
+        self.registers[Register::X.index()]
+    }
+
+    fn halted(&self) -> bool {
+        self.operation.is_none() && self.pc >= self.program.len()
+    }
+
     fn tick(&mut self) {
         self.operation = match self.operation.take() {
             None => self.next_operation(),
-            Some((op, 0)) => {
-                self.do_operation(op);
+            Some((op, address, 0)) => {
+                self.do_operation(op, address);
                 self.next_operation()
             }
-            Some((op, num)) => Some((op, num - 1)),
+            Some((op, address, num)) => Some((op, address, num - 1)),
         };
         self.clock += 1;
     }
 
-    fn do_operation(&mut self, op: Operation) {
-        if let Operation::AddX(num) = op {
-            self.register += num
+    fn value(&self, operand: Operand) -> i32 {
+        match operand {
+            Operand::Value(value) => value,
+            Operand::Register(register) => self.registers[register.index()],
         }
     }
 
-    fn next_operation(&mut self) -> Option<(Operation, usize)> {
-        self.stream.next().map(|op| (op, op.ticks()))
+    // Registers wrap around on overflow, so a runaway program can't crash it.
+    fn do_operation(&mut self, op: Operation, address: usize) {
+        match op {
+            Operation::Noop => (),
+            Operation::Add(r, operand) => {
+                let value = self.value(operand);
+                self.registers[r.index()] = self.registers[r.index()].wrapping_add(value)
+            }
+            Operation::Mul(r, operand) => {
+                let value = self.value(operand);
+                self.registers[r.index()] = self.registers[r.index()].wrapping_mul(value)
+            }
+            Operation::Set(r, operand) => self.registers[r.index()] = self.value(operand),
+            Operation::Jump(to) => self.jump(address, to),
+            Operation::Branch(condition, r, to) => {
+                if condition.holds(self.registers[r.index()]) {
+                    self.jump(address, to)
+                }
+            }
+        }
+    }
+
+    // Jumping before the start of the program halts it, like running past the end.
+    fn jump(&mut self, address: usize, offset: i32) {
+        self.pc = usize::try_from(address as i64 + offset as i64).unwrap_or(usize::MAX);
+    }
+
+    fn next_operation(&mut self) -> Option<(Operation, usize, usize)> {
+        let op = *self.program.get(self.pc)?;
+        let address = self.pc;
+        self.pc += 1;
+        Some((op, address, op.cycles() - 1))
     }
 }
 
@@ -101,11 +457,47 @@ impl Crt {
         }
     }
 
-    fn draw(&mut self, clock: usize, sprite_pos: i32) {
+    fn can_draw(clock: usize) -> bool {
+        (1..=WIDTH * HEIGHT).contains(&clock)
+    }
+
+    fn pixel(clock: usize, sprite_pos: i32) -> char {
+        let col = ((clock - 1) % WIDTH) as i32;
+        if sprite_pos - 1 <= col && col <= sprite_pos + 1 {
+            '#'
+        } else {
+            '.'
+        }
+    }
+
+    fn draw(&mut self, clock: usize, sprite_pos: i32) -> char {
         let row = (clock - 1) / WIDTH;
         let col = (clock - 1) % WIDTH;
-        if sprite_pos - 1 <= col as i32 && col as i32 <= sprite_pos + 1 {
-            self.display[row][col] = '#';
+        let pixel = Self::pixel(clock, sprite_pos);
+        if pixel == '#' {
+            self.display[row][col] = pixel;
+        }
+        pixel
+    }
+
+    fn read(&self) -> Result<String, UnknownGlyphs> {
+        let mut letters = String::new();
+        let mut unknown = vec![];
+        for column in (0..WIDTH).step_by(GLYPH_SPACING) {
+            let glyph = self
+                .display
+                .iter()
+                .map(|row| row[column..column + GLYPH_WIDTH].iter().collect::<String>())
+                .collect::<Vec<_>>();
+            match FONT.iter().find(|(_, rows)| glyph == rows) {
+                Some((letter, _)) => letters.push(*letter),
+                None => unknown.push(column),
+            }
+        }
+        if unknown.is_empty() {
+            Ok(letters)
+        } else {
+            Err(UnknownGlyphs { columns: unknown })
         }
     }
 
@@ -116,3 +508,262 @@ impl Crt {
             .fold(String::new(), |acc, row| acc + "\n" + &row)
     }
 }
+
+type Comparison = (&'static str, fn(&i32, &i32) -> bool);
+
+const COMPARISONS: [Comparison; 6] = [
+    ("==", i32::eq),
+    ("!=", i32::ne),
+    ("<", i32::lt),
+    ("<=", i32::le),
+    (">", i32::gt),
+    (">=", i32::ge),
+];
+
+enum Breakpoint {
+    Clock(usize),
+    // stops when the comparison becomes true, rather than on every cycle it holds.
+    Register(Register, Comparison, i32),
+}
+
+impl Breakpoint {
+    fn parse(args: &[&str]) -> Result<Self, String> {
+        match args {
+            [clock] => clock
+                .parse::<usize>()
+                .map(Self::Clock)
+                .map_err(|_| format!("'{clock}' is not a clock value")),
+            [register, comparison, value] => {
+                let comparison = COMPARISONS
+                    .iter()
+                    .find(|(symbol, _)| symbol == comparison)
+                    .ok_or(format!("unknown comparison '{comparison}'"))?;
+                let value = value
+                    .parse::<i32>()
+                    .map_err(|_| format!("'{value}' is not a number"))?;
+                Ok(Self::Register(
+                    Register::from_name(register)?,
+                    *comparison,
+                    value,
+                ))
+            }
+            _ => Err("expected `break <clock>` or `break <register> <cmp> <value>`".to_string()),
+        }
+    }
+
+    fn holds(&self, cpu: &Cpu) -> bool {
+        match self {
+            Self::Clock(clock) => cpu.clock == *clock,
+            Self::Register(register, (_, compare), value) => {
+                compare(&cpu.registers[register.index()], value)
+            }
+        }
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Clock(clock) => write!(f, "clock {clock}"),
+            Self::Register(register, (symbol, _), value) => {
+                write!(f, "{register} {symbol} {value}")
+            }
+        }
+    }
+}
+
+struct Debugger {
+    cpu: Cpu,
+    crt: Crt,
+    // each breakpoint, and whether it held after the last tick.
+    breakpoints: Vec<(Breakpoint, bool)>,
+}
+
+impl Debugger {
+    fn new(program: Vec<Operation>) -> Self {
+        Self {
+            cpu: Cpu::new(program),
+            crt: Crt::new(),
+            breakpoints: vec![],
+        }
+    }
+
+    fn repl(&mut self) {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            print!("(cpu) ");
+            io::stdout().flush().expect("should flush stdout");
+            let Some(Ok(line)) = lines.next() else { break };
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words[..] {
+                [] => continue,
+                ["q" | "quit"] => break,
+                ["s" | "step"] => self.step(1),
+                ["s" | "step", n] => match n.parse::<usize>() {
+                    Ok(n) => self.step(n),
+                    Err(_) => println!("'{n}' is not a number of steps"),
+                },
+                ["c" | "continue"] => self.resume(),
+                ["b" | "break", ref args @ ..] => match Breakpoint::parse(args) {
+                    Ok(breakpoint) => {
+                        let holds = breakpoint.holds(&self.cpu);
+                        println!("breakpoint {}: {breakpoint}", self.breakpoints.len());
+                        self.breakpoints.push((breakpoint, holds));
+                    }
+                    Err(err) => println!("{err}"),
+                },
+                ["d" | "delete"] => self.breakpoints.clear(),
+                ["i" | "info"] => {
+                    for (i, (breakpoint, _)) in self.breakpoints.iter().enumerate() {
+                        println!("breakpoint {i}: {breakpoint}");
+                    }
+                }
+                ["p" | "print"] => println!("{}", self.state()),
+                ["crt"] => println!("{}", self.crt.output()),
+                _ => println!(
+                    "commands: step [n], continue, break <clock>, break <register> <cmp> <value>, \
+                     delete, info, print, crt, quit"
+                ),
+            }
+        }
+    }
+
+    fn state(&self) -> String {
+        let registers = REGISTERS
+            .iter()
+            .map(|r| format!("{r}={}", self.cpu.registers[r.index()]))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let operation = match self.cpu.operation {
+            Some((op, address, left)) => format!("{op} @ {address} ({left} cycles left)"),
+            None if self.cpu.halted() => "halted".to_string(),
+            None => "idle".to_string(),
+        };
+        format!("clock {}: {registers} | {operation}", self.cpu.clock)
+    }
+
+    // Ticks once, returning the breakpoints that were hit.
+    fn tick(&mut self) -> Vec<usize> {
+        self.cpu.tick();
+        if Crt::can_draw(self.cpu.clock) {
+            self.crt.draw(self.cpu.clock, self.cpu.register());
+        }
+        let mut hit = vec![];
+        for (i, (breakpoint, held)) in self.breakpoints.iter_mut().enumerate() {
+            let holds = breakpoint.holds(&self.cpu);
+            let triggered = match breakpoint {
+                Breakpoint::Clock(_) => holds,
+                Breakpoint::Register(..) => holds && !*held,
+            };
+            if triggered {
+                hit.push(i);
+            }
+            *held = holds;
+        }
+        hit
+    }
+
+    fn step(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.tick();
+        }
+        println!("{}", self.state());
+    }
+
+    // Runs until a breakpoint is hit or the program halts, giving up after
+    // `MAX_CYCLES` so a program that loops forever can't hang the debugger.
+    fn resume(&mut self) {
+        let mut cycles = 0;
+        while !self.cpu.halted() {
+            if cycles == MAX_CYCLES {
+                println!("no breakpoint hit after {MAX_CYCLES} cycles, stopping");
+                break;
+            }
+            cycles += 1;
+            let hit = self.tick();
+            if !hit.is_empty() {
+                for i in hit {
+                    println!("hit breakpoint {i}: {}", self.breakpoints[i].0);
+                }
+                break;
+            }
+        }
+        println!("{}", self.state());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disassembly_round_trips() {
+        let source = "
+            noop
+            addx -3
+            addy x
+            mulz 7
+            mulx y
+            setx 0
+            sety z ; comments are dropped
+            jmp -2
+            jz x 3
+            jnz y -1
+            jgz z 0
+            jlz x 12
+        ";
+        let program = assemble(source).expect("should assemble");
+        assert_eq!(program.len(), 12);
+        let listing = disassemble(&program);
+        assert_eq!(assemble(&listing).expect("should assemble again"), program);
+    }
+
+    fn display(letters: &str) -> Crt {
+        let mut crt = Crt::new();
+        for (i, letter) in letters.chars().enumerate() {
+            let (_, rows) = FONT
+                .iter()
+                .find(|(l, _)| *l == letter)
+                .expect("should be in the font");
+            for (row, pixels) in rows.iter().enumerate() {
+                for (col, pixel) in pixels.chars().enumerate() {
+                    crt.display[row][i * GLYPH_SPACING + col] = pixel;
+                }
+            }
+        }
+        crt
+    }
+
+    #[test]
+    fn reads_letters() {
+        assert_eq!(
+            display("EALGULPG").read().ok(),
+            Some("EALGULPG".to_string())
+        );
+        assert_eq!(
+            display("ZSROKJIH").read().ok(),
+            Some("ZSROKJIH".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_unknown_glyphs() {
+        let mut crt = display("EALGULPG");
+        // fills in the top left corner of the G, which no letter looks like.
+        crt.display[0][3 * GLYPH_SPACING] = '#';
+        let err = crt.read().expect_err("should not read the altered glyph");
+        assert_eq!(err.columns, vec![3 * GLYPH_SPACING]);
+    }
+
+    #[test]
+    fn registers_wrap_around() {
+        let program =
+            assemble("setx 2147483647\naddx 1\nsety -2147483648\nmuly 3").expect("should assemble");
+        let mut cpu = Cpu::new(program);
+        while !cpu.halted() {
+            cpu.tick();
+        }
+        assert_eq!(cpu.registers, [i32::MIN, i32::MIN, 0]);
+    }
+}
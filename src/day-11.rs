@@ -1,21 +1,89 @@
+use num::{BigUint, CheckedSub, Integer};
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Display},
+    fs::read_to_string,
+};
+
+const DEFAULT_TOP: usize = 2;
+
 fn main() {
-    #[rustfmt::skip]
-    let mut monkeys_p1 = [
-        Monkey::new(vec![91, 58, 52, 69, 95, 54], |old| old * 13, 7, 1, 5),
-        Monkey::new(vec![80, 80, 97, 84], |old| old * old, 3, 3, 5),
-        Monkey::new(vec![86, 92, 71], |old| old + 7, 2, 0, 4),
-        Monkey::new(vec![96, 90, 99, 76, 79, 85, 98, 61], |old| old + 4, 11, 7, 6),
-        Monkey::new(vec![60, 83, 68, 64, 73], |old| old * 19, 17, 1, 0),
-        Monkey::new(vec![96, 52, 52, 94, 76, 51, 57], |old| old + 3, 5, 7, 3),
-        Monkey::new(vec![75], |old| old + 5, 13, 4, 2),
-        Monkey::new(vec![83, 75], |old| old + 1, 19, 2, 6),
-    ];
-    let mut monkeys_p2 = monkeys_p1.clone();
+    let mut top = DEFAULT_TOP;
+    let mut show = false;
+    let (mut exact, mut routes, mut extrapolate) = (None, None, None);
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next_number = || {
+            args.next()
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or_else(|| panic!("{arg} should be followed by a number"))
+        };
+        match arg.as_str() {
+            "--top" => top = next_number(),
+            "--exact" => exact = Some(next_number()),
+            "--routes" => routes = Some(next_number()),
+            "--extrapolate" => extrapolate = Some(next_number()),
+            "--show" => show = true,
+            _ => panic!(
+                "usage: day-11 [--top <k>] [--show] [--exact <rounds>] [--routes <rounds>] \
+                 [--extrapolate <rounds>]"
+            ),
+        }
+    }
+    let input = read_to_string("inputs/day-11.txt").expect("missing input file");
+    let monkeys = parse::input(input);
+    let mut monkeys_p1 = monkeys.clone();
+    let mut monkeys_p2 = monkeys.clone();
+    if show {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            println!("Monkey {i}: new = {}", monkey.operation);
+            println!("  {:?}", monkey.operation);
+        }
+    }
 
     play_rounds(&mut monkeys_p1, 20, 3);
-    println!("Part 1: {}", most_active_product(&monkeys_p1));
+    println!("Part 1: {}", most_active_product(&monkeys_p1, top));
     play_rounds(&mut monkeys_p2, 10_000, 1);
-    println!("Part 2: {}", most_active_product(&monkeys_p2));
+    println!("Part 2: {}", most_active_product(&monkeys_p2, top));
+
+    if let Some(rounds) = exact {
+        if worry_factor(&monkeys, 1).is_none() {
+            println!(
+                "Worry is kept whole while a monkey divides, so there is no reduction to check"
+            );
+        } else {
+            match first_route_mismatch(&monkeys, rounds) {
+                None => println!("Reduced and exact worry agree over {rounds} rounds"),
+                Some((item, round)) => println!(
+                    "Reduced and exact worry disagree for item {item} in round {}",
+                    round + 1
+                ),
+            }
+        }
+    }
+    if let Some(rounds) = routes {
+        for (item, (holder, worry)) in starting_items(&monkeys).into_iter().enumerate() {
+            let route = item_routes(&monkeys, holder, worry, rounds, 1)
+                .iter()
+                .map(|visited| format!("{visited:?}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!("Item {item} ({worry} at monkey {holder}): {route}");
+        }
+    }
+    if let Some(rounds) = extrapolate {
+        let mut inspections = extrapolated_inspections(&monkeys, rounds);
+        inspections.sort_by(|a, b| b.cmp(a));
+        println!(
+            "Monkey business after {rounds} rounds: {}",
+            inspections
+                .iter()
+                .take(top)
+                .map(|n| BigUint::from(*n))
+                .product::<BigUint>()
+        );
+    }
 }
 
 // just some aliases to determine what each usize is;
@@ -23,20 +91,159 @@ type Worry = usize;
 type Id = usize;
 type Throw = (Worry, Id);
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    fn from(raw: char) -> Self {
+        match raw {
+            '+' => Op::Add,
+            '-' => Op::Sub,
+            '*' => Op::Mul,
+            '/' => Op::Div,
+            _ => panic!("impossible"),
+        }
+    }
+
+    // None when worry would go below zero, which reduced worry can't tell.
+    fn calculate_exact(&self, lhs: &BigUint, rhs: &BigUint) -> Option<BigUint> {
+        match self {
+            Op::Add => Some(lhs + rhs),
+            Op::Sub => lhs.checked_sub(rhs),
+            Op::Mul => Some(lhs * rhs),
+            Op::Div => Some(lhs / rhs),
+        }
+    }
+
+    // Worry is kept modulo `worry_factor` when there is one, and must fit in
+    // a `Worry` when there isn't.
+    fn calculate(&self, lhs: Worry, rhs: Worry, worry_factor: Option<usize>) -> Worry {
+        let result = match (self, worry_factor) {
+            (Op::Sub, Some(factor)) => Some((lhs % factor + factor - rhs % factor) % factor),
+            (Op::Sub, None) => Some(
+                lhs.checked_sub(rhs)
+                    .unwrap_or_else(|| panic!("worry can't go below zero ({lhs} - {rhs})")),
+            ),
+            (Op::Add, _) => lhs.checked_add(rhs),
+            (Op::Mul, _) => lhs.checked_mul(rhs),
+            (Op::Div, _) => Some(lhs / rhs),
+        };
+        result.unwrap_or_else(|| panic!("worry got too high ({lhs} {} {rhs})", self.symbol()))
+    }
+
+    fn precedence(&self) -> usize {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Expr {
+    Old,
+    Value(Worry),
+    BinOp(Box<Expr>, Op, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, old: Worry, worry_factor: Option<usize>) -> Worry {
+        match self {
+            Expr::Old => old,
+            Expr::Value(value) => *value,
+            Expr::BinOp(lhs, op, rhs) => op.calculate(
+                lhs.eval(old, worry_factor),
+                rhs.eval(old, worry_factor),
+                worry_factor,
+            ),
+        }
+    }
+
+    fn divides(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Value(_) => false,
+            Expr::BinOp(lhs, op, rhs) => *op == Op::Div || lhs.divides() || rhs.divides(),
+        }
+    }
+
+    fn eval_exact(&self, old: &BigUint) -> Option<BigUint> {
+        match self {
+            Expr::Old => Some(old.clone()),
+            Expr::Value(value) => Some(BigUint::from(*value)),
+            Expr::BinOp(lhs, op, rhs) => {
+                op.calculate_exact(&lhs.eval_exact(old)?, &rhs.eval_exact(old)?)
+            }
+        }
+    }
+
+    fn precedence(&self) -> usize {
+        match self {
+            Expr::BinOp(_, op, _) => op.precedence(),
+            _ => usize::MAX,
+        }
+    }
+}
+
+// Prints with only the parentheses needed to keep the same meaning.
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Value(value) => write!(f, "{value}"),
+            Expr::BinOp(lhs, op, rhs) => {
+                let left_parens = lhs.precedence() < op.precedence();
+                // operators go left to right, so one of the same precedence on
+                // the right needs them too, unless both are `+` or both are `*`.
+                let right_parens = match &**rhs {
+                    Expr::BinOp(_, rhs_op, _) if rhs_op.precedence() == op.precedence() => {
+                        !(rhs_op == op && matches!(op, Op::Add | Op::Mul))
+                    }
+                    _ => rhs.precedence() < op.precedence(),
+                };
+                if left_parens {
+                    write!(f, "({lhs})")?;
+                } else {
+                    write!(f, "{lhs}")?;
+                }
+                write!(f, " {} ", op.symbol())?;
+                if right_parens {
+                    write!(f, "({rhs})")
+                } else {
+                    write!(f, "{rhs}")
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 struct Monkey {
     items: Vec<Worry>,
     divisible_by: usize,
     if_true: Id,
     if_false: Id,
-    operation: fn(Worry) -> Worry,
+    operation: Expr,
     inspections: usize,
 }
 
 impl Monkey {
     fn new(
         items: Vec<Worry>,
-        operation: fn(Worry) -> Worry,
+        operation: Expr,
         divisible_by: usize,
         if_true: Id,
         if_false: Id,
@@ -51,42 +258,312 @@ impl Monkey {
         }
     }
 
-    fn take_turn(&mut self, stress_relief: usize, worry_factor: usize) -> Vec<Throw> {
+    fn take_turn(&mut self, stress_relief: usize, worry_factor: Option<usize>) -> Vec<Throw> {
         self.inspections += self.items.len();
-        self.items
-            .drain(..)
-            .map(|worry| {
-                let new_worry = (self.operation)(worry) / stress_relief % worry_factor;
-                let new_id = if new_worry % self.divisible_by == 0 {
-                    self.if_true
-                } else {
-                    self.if_false
-                };
-                (new_worry, new_id)
-            })
+        let items = self.items.drain(..).collect::<Vec<_>>();
+        items
+            .into_iter()
+            .map(|worry| self.inspect(worry, stress_relief, worry_factor))
             .collect()
     }
 
+    fn inspect(&self, worry: Worry, stress_relief: usize, worry_factor: Option<usize>) -> Throw {
+        let new_worry = reduce(
+            self.operation.eval(worry, worry_factor) / stress_relief,
+            worry_factor,
+        );
+        (
+            new_worry,
+            self.target(new_worry.is_multiple_of(self.divisible_by)),
+        )
+    }
+
+    // Same as `inspect`, but keeping the whole worry value.
+    fn inspect_exact(&self, worry: &BigUint, stress_relief: usize) -> Option<(BigUint, Id)> {
+        let new_worry = self.operation.eval_exact(worry)? / stress_relief;
+        let divisible = new_worry.is_multiple_of(&BigUint::from(self.divisible_by));
+        Some((new_worry, self.target(divisible)))
+    }
+
+    fn target(&self, divisible: bool) -> Id {
+        if divisible {
+            self.if_true
+        } else {
+            self.if_false
+        }
+    }
+
     fn take_worry(&mut self, worry: Worry) {
         self.items.push(worry);
     }
 }
 
-fn play_rounds(monkeys: &mut [Monkey; 8], rounds: usize, stress_relief: usize) {
-    let worry_factor = monkeys.iter().map(|m| m.divisible_by).product();
+fn play_rounds(monkeys: &mut [Monkey], rounds: usize, stress_relief: usize) {
+    let worry_factor = worry_factor(monkeys, stress_relief);
     for _ in 0..rounds {
-        for i in 0..8 {
+        for i in 0..monkeys.len() {
             let monkey = &mut monkeys[i];
             let throws = monkey.take_turn(stress_relief, worry_factor);
             for (worry, id) in throws {
-                monkeys[id].take_worry(worry);
+                monkeys
+                    .get_mut(id)
+                    .unwrap_or_else(|| panic!("monkey {i} throws to missing monkey {id}"))
+                    .take_worry(worry);
             }
         }
     }
 }
 
-fn most_active_product(monkeys: &[Monkey; 8]) -> usize {
+// Product of the inspections of the `top` most active monkeys.
+fn most_active_product(monkeys: &[Monkey], top: usize) -> usize {
     let mut inspections = monkeys.iter().map(|m| m.inspections).collect::<Vec<_>>();
-    inspections.sort();
-    inspections[7] * inspections[6]
+    inspections.sort_by(|a, b| b.cmp(a));
+    inspections.iter().take(top).product()
+}
+
+mod parse {
+    use super::{Expr, Id, Monkey, Op, Worry};
+
+    use nom::{
+        branch::alt,
+        bytes::complete::tag,
+        character::complete::{newline, one_of, space0, u64},
+        multi::{many0, separated_list0, separated_list1},
+        sequence::{delimited, preceded, tuple},
+        IResult as NomResult, Parser,
+    };
+
+    // Panics with the line that can't be read, rather than leaving out the
+    // monkeys from there on.
+    pub(crate) fn input(input: String) -> Vec<Monkey> {
+        let input = input.replace("\r\n", "\n");
+        let (rest, monkeys) = monkeys(&input).expect("should be parseable");
+        if !rest.trim().is_empty() {
+            let rest = rest.trim_start();
+            let failed = match monkey(rest) {
+                Err(nom::Err::Error(e) | nom::Err::Failure(e)) => e.input,
+                _ => rest,
+            };
+            let offset = input.len() - failed.len();
+            let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let line = input[line_start..].lines().next().unwrap_or("");
+            panic!(
+                "can't read line {}: '{line}'",
+                input[..offset].matches('\n').count() + 1
+            );
+        }
+        monkeys
+            .into_iter()
+            .enumerate()
+            .map(|(i, (id, monkey))| {
+                assert_eq!(i, id, "monkeys should be listed in order");
+                monkey
+            })
+            .collect()
+    }
+
+    fn monkeys(input: &str) -> NomResult<&str, Vec<(Id, Monkey)>> {
+        separated_list0(newline.and(newline), monkey)(input)
+    }
+
+    fn monkey(input: &str) -> NomResult<&str, (Id, Monkey)> {
+        let (input, id) = delimited(tag("Monkey "), number, tag(":\n"))(input)?;
+        let (input, items) = delimited(
+            tag("  Starting items: "),
+            separated_list1(tag(", "), number),
+            newline,
+        )(input)?;
+        let (input, operation) = delimited(tag("  Operation: new = "), expr, newline)(input)?;
+        let (input, divisible_by) =
+            delimited(tag("  Test: divisible by "), number, newline)(input)?;
+        let (input, if_true) =
+            delimited(tag("    If true: throw to monkey "), number, newline)(input)?;
+        let (input, if_false) = preceded(tag("    If false: throw to monkey "), number)(input)?;
+        Ok((
+            input,
+            (
+                id,
+                Monkey::new(items, operation, divisible_by, if_true, if_false),
+            ),
+        ))
+    }
+
+    // Usual precedence: `*` and `/` bind tighter than `+` and `-`, and
+    // operators of the same precedence go left to right.
+    fn expr(input: &str) -> NomResult<&str, Expr> {
+        binary(input, "+-", term)
+    }
+
+    fn term(input: &str) -> NomResult<&str, Expr> {
+        binary(input, "*/", factor)
+    }
+
+    fn binary<'a>(
+        input: &'a str,
+        ops: &'static str,
+        operand: fn(&'a str) -> NomResult<&'a str, Expr>,
+    ) -> NomResult<&'a str, Expr> {
+        let (input, first) = operand(input)?;
+        let (input, rest) = many0(tuple((delimited(space0, one_of(ops), space0), operand)))(input)?;
+        let expr = rest.into_iter().fold(first, |lhs, (op, rhs)| {
+            Expr::BinOp(Box::new(lhs), Op::from(op), Box::new(rhs))
+        });
+        Ok((input, expr))
+    }
+
+    fn factor(input: &str) -> NomResult<&str, Expr> {
+        alt((
+            tag("old").map(|_| Expr::Old),
+            number.map(Expr::Value),
+            delimited(tag("(").and(space0), expr, space0.and(tag(")"))),
+        ))(input)
+    }
+
+    fn number(input: &str) -> NomResult<&str, Worry> {
+        u64.map(|n| n as Worry).parse(input)
+    }
+}
+
+// Items are numbered by where they start, going through the monkeys in order.
+fn starting_items(monkeys: &[Monkey]) -> Vec<(Id, Worry)> {
+    monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(id, m)| m.items.iter().map(move |worry| (id, *worry)))
+        .collect()
+}
+
+// Items never affect each other, so each can be followed on its own. Monkeys
+// take turns in order, so an item thrown to a later monkey is inspected again
+// in the same round, while one thrown back waits for the next round. Returns
+// the monkeys that inspected the item, in order, and where it ends the round.
+fn item_round(
+    monkeys: &[Monkey],
+    mut holder: Id,
+    mut worry: Worry,
+    stress_relief: usize,
+    worry_factor: Option<usize>,
+) -> (Vec<Id>, Id, Worry) {
+    let mut visited = vec![];
+    loop {
+        visited.push(holder);
+        let (new_worry, to) = monkeys[holder].inspect(worry, stress_relief, worry_factor);
+        let round_over = to <= holder;
+        (holder, worry) = (to, new_worry);
+        if round_over {
+            return (visited, holder, worry);
+        }
+    }
+}
+
+// Same as `item_round`, but None if the worry goes below zero on the way.
+fn item_round_exact(
+    monkeys: &[Monkey],
+    mut holder: Id,
+    mut worry: BigUint,
+    stress_relief: usize,
+) -> Option<(Vec<Id>, Id, BigUint)> {
+    let mut visited = vec![];
+    loop {
+        visited.push(holder);
+        let (new_worry, to) = monkeys[holder].inspect_exact(&worry, stress_relief)?;
+        let round_over = to <= holder;
+        (holder, worry) = (to, new_worry);
+        if round_over {
+            return Some((visited, holder, worry));
+        }
+    }
+}
+
+// Every test only needs the worry modulo the product of the divisors, so worry
+// can be kept down to that. Division is the exception: its result depends on
+// more than the remainder, so when any monkey divides, or relief divides the
+// worry after each inspection, the worry is kept whole.
+fn worry_factor(monkeys: &[Monkey], stress_relief: usize) -> Option<usize> {
+    if stress_relief != 1 || monkeys.iter().any(|m| m.operation.divides()) {
+        None
+    } else {
+        Some(monkeys.iter().map(|m| m.divisible_by).product())
+    }
+}
+
+fn reduce(worry: Worry, worry_factor: Option<usize>) -> Worry {
+    worry_factor.map_or(worry, |factor| worry % factor)
+}
+
+// The monkeys that inspect an item in each round.
+fn item_routes(
+    monkeys: &[Monkey],
+    mut holder: Id,
+    mut worry: Worry,
+    rounds: usize,
+    stress_relief: usize,
+) -> Vec<Vec<Id>> {
+    let worry_factor = worry_factor(monkeys, stress_relief);
+    (0..rounds)
+        .map(|_| {
+            let visited;
+            (visited, holder, worry) =
+                item_round(monkeys, holder, worry, stress_relief, worry_factor);
+            visited
+        })
+        .collect()
+}
+
+// Follows every item with both reduced and exact worry, returning the first
+// item and round where they are thrown differently, or where exact worry goes
+// below zero while reduced worry wraps around. Worry is only reduced without
+// stress relief, so that is what is checked.
+fn first_route_mismatch(monkeys: &[Monkey], rounds: usize) -> Option<(usize, usize)> {
+    for (item, (holder, worry)) in starting_items(monkeys).into_iter().enumerate() {
+        let routes = item_routes(monkeys, holder, worry, rounds, 1);
+        let (mut holder, mut worry) = (holder, BigUint::from(worry));
+        for (round, route) in routes.iter().enumerate() {
+            let visited;
+            (visited, holder, worry) = match item_round_exact(monkeys, holder, worry, 1) {
+                Some(exact) => exact,
+                None => return Some((item, round)),
+            };
+            if &visited != route {
+                return Some((item, round));
+            }
+        }
+    }
+    None
+}
+
+// Inspections per monkey after any number of rounds without stress relief.
+// With worry kept down by the divisors, an item's state at the start of a
+// round (who holds it, and its worry) must eventually repeat, after which its
+// route repeats as well, so only the rounds up to that point are played.
+// Without that, the states may never repeat and every round is played. Counts
+// can go well past a `usize` over that many rounds.
+fn extrapolated_inspections(monkeys: &[Monkey], rounds: usize) -> Vec<u128> {
+    let worry_factor = worry_factor(monkeys, 1);
+    let mut inspections = vec![0; monkeys.len()];
+    for (holder, worry) in starting_items(monkeys) {
+        let mut state = (holder, reduce(worry, worry_factor));
+        let mut seen = HashMap::new();
+        let mut routes: Vec<Vec<Id>> = vec![];
+        for round in 0..rounds {
+            if let Some(start) = seen.insert(state, round) {
+                let cycle = &routes[start..round];
+                let remaining = rounds - round;
+                for (i, route) in cycle.iter().enumerate() {
+                    let repeats = remaining / cycle.len() + (i < remaining % cycle.len()) as usize;
+                    for id in route {
+                        inspections[*id] += repeats as u128;
+                    }
+                }
+                break;
+            }
+            let (visited, holder, worry) = item_round(monkeys, state.0, state.1, 1, worry_factor);
+            for id in &visited {
+                inspections[*id] += 1;
+            }
+            routes.push(visited);
+            state = (holder, worry);
+        }
+    }
+    inspections
 }
@@ -1,26 +1,70 @@
-use std::{collections::HashMap, fs::read_to_string};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, VecDeque},
+    env,
+    fs::read_to_string,
+};
+
+// The puzzle's rules: one step up at most, any step down, no diagonals, and
+// every step costs the same.
+const PUZZLE_RULES: Rules = Rules {
+    max_ascent: 1,
+    max_descent: u32::MAX,
+    diagonal: false,
+    climb_cost: 0,
+};
 
 fn main() {
+    let mut rules = PUZZLE_RULES;
+    let mut render = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next_number = || {
+            args.next()
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or_else(|| panic!("{arg} should be followed by a number"))
+        };
+        match arg.as_str() {
+            "--ascent" => rules.max_ascent = next_number(),
+            "--descent" => rules.max_descent = next_number(),
+            "--climb-cost" => rules.climb_cost = next_number() as usize,
+            "--diagonal" => rules.diagonal = true,
+            "--render" => render = true,
+            _ => panic!(
+                "usage: day-12 [--ascent <n>] [--descent <n>] [--climb-cost <n>] [--diagonal] \
+                 [--render]"
+            ),
+        }
+    }
     let input = read_to_string("inputs/day-12.txt").expect("missing input file");
     let (start, end, grid) = parse_input(input);
-    let starting_points = grid
+    let route = shortest_path(start, end, &grid, &rules);
+    println!("Part 1: {}", describe(&route));
+    let visits = visits_to(end, &grid, &rules);
+    let best_start = grid
         .iter()
         .filter(|(_, p)| p.elevation == 0)
-        .map(|(c, _)| c);
-    println!("Part 1: {}", shortest_path(start, end, &mut grid.clone()));
-    println!(
-        "Part 2: {}",
-        starting_points
-            .map(|s| shortest_path(*s, end, &mut grid.clone()))
-            .min()
-            .expect("for sure there is a min")
-    );
+        .filter_map(|(c, _)| visits.get(c).map(|v| (v.cost, *c)))
+        .min();
+    let hiking_route = best_start.map(|(cost, c)| (cost, route_to(c, &visits)));
+    println!("Part 2: {}", describe(&hiking_route));
+    if render {
+        for (_, route) in [route, hiking_route].iter().flatten() {
+            println!("\n{}", render_route(route, &grid));
+        }
+    }
+}
+
+fn describe(route: &Option<(usize, Vec<Coords>)>) -> String {
+    match route {
+        Some((cost, _)) => cost.to_string(),
+        None => "unreachable".to_string(),
+    }
 }
 
 #[derive(Clone, Copy)]
 struct Point {
     elevation: u32,
-    min_distance: usize,
 }
 type Coords = (i32, i32);
 type Grid = HashMap<Coords, Point>;
@@ -44,41 +88,195 @@ fn parse_input(input: String) -> (Coords, Coords, Grid) {
                 }
                 c => c as u32 - 'a' as u32,
             };
-            grid.insert(
-                (x, y),
-                Point {
-                    elevation,
-                    min_distance: usize::MAX,
-                },
-            );
+            grid.insert((x, y), Point { elevation });
         }
     }
     (start, end, grid)
 }
 
-fn shortest_path(start: Coords, end: Coords, grid: &mut Grid) -> usize {
-    let mut to_visit = vec![start];
-    grid.entry(start).and_modify(|e| e.min_distance = 0);
-    while let Some(point) = to_visit.pop() {
-        let distance = grid[&point].min_distance + 1;
-        for neighbor in get_neighbors(point, grid) {
-            to_visit.push(neighbor);
-            grid.entry(neighbor)
-                .and_modify(|e| e.min_distance = distance);
+#[derive(Clone, Copy)]
+struct Rules {
+    max_ascent: u32,
+    max_descent: u32,
+    diagonal: bool,
+    // added to the cost of a step for each unit of elevation it changes, on
+    // top of the one every step costs.
+    climb_cost: usize,
+}
+
+impl Rules {
+    fn can_move(&self, from: &Point, to: &Point) -> bool {
+        to.elevation <= from.elevation.saturating_add(self.max_ascent)
+            && from.elevation <= to.elevation.saturating_add(self.max_descent)
+    }
+
+    fn cost(&self, from: &Point, to: &Point) -> usize {
+        1 + self.climb_cost * from.elevation.abs_diff(to.elevation) as usize
+    }
+
+    // The same rules for walking a route backwards, where ascents are descents.
+    fn reversed(&self) -> Self {
+        Self {
+            max_ascent: self.max_descent,
+            max_descent: self.max_ascent,
+            ..*self
+        }
+    }
+
+    fn offsets(&self) -> &'static [Coords] {
+        if self.diagonal {
+            &[
+                (-1, 0),
+                (1, 0),
+                (0, -1),
+                (0, 1),
+                (-1, -1),
+                (-1, 1),
+                (1, -1),
+                (1, 1),
+            ]
+        } else {
+            &[(-1, 0), (1, 0), (0, -1), (0, 1)]
         }
     }
-    grid[&end].min_distance
 }
 
-fn get_neighbors((x, y): Coords, grid: &Grid) -> Vec<Coords> {
+#[derive(Clone, Copy)]
+struct Visit {
+    cost: usize,
+    // where the search came from to get here.
+    previous: Option<Coords>,
+}
+
+// The cost and the coords from start to end, both included, or None if end is
+// unreachable.
+fn shortest_path(
+    start: Coords,
+    end: Coords,
+    grid: &Grid,
+    rules: &Rules,
+) -> Option<(usize, Vec<Coords>)> {
+    let visits = search(start, grid, rules);
+    let cost = visits.get(&end)?.cost;
+    let mut route = route_to(end, &visits);
+    route.reverse();
+    Some((cost, route))
+}
+
+// Every point that can reach `end`, found by walking down from it with the
+// rules reversed, so following `previous` leads up to `end`. Costs don't
+// depend on the direction of a step, so they stay the same.
+fn visits_to(end: Coords, grid: &Grid, rules: &Rules) -> HashMap<Coords, Visit> {
+    search(end, grid, &rules.reversed())
+}
+
+// Steps all cost the same unless climbing adds to them, and only then is
+// Dijkstra needed.
+fn search(source: Coords, grid: &Grid, rules: &Rules) -> HashMap<Coords, Visit> {
+    match rules.climb_cost {
+        0 => breadth_first(source, grid, rules),
+        _ => dijkstra(source, grid, rules),
+    }
+}
+
+// Follows the visits back from a point to where the search started.
+fn route_to(point: Coords, visits: &HashMap<Coords, Visit>) -> Vec<Coords> {
+    let mut route = vec![point];
+    while let Some(previous) = visits[route.last().expect("starts with a point")].previous {
+        route.push(previous);
+    }
+    route
+}
+
+fn breadth_first(source: Coords, grid: &Grid, rules: &Rules) -> HashMap<Coords, Visit> {
+    let mut visits = HashMap::from([(
+        source,
+        Visit {
+            cost: 0,
+            previous: None,
+        },
+    )]);
+    let mut to_visit = VecDeque::from([source]);
+    while let Some(point) = to_visit.pop_front() {
+        let cost = visits[&point].cost + 1;
+        for neighbor in get_neighbors(point, grid, rules) {
+            if let Entry::Vacant(e) = visits.entry(neighbor) {
+                e.insert(Visit {
+                    cost,
+                    previous: Some(point),
+                });
+                to_visit.push_back(neighbor);
+            }
+        }
+    }
+    visits
+}
+
+fn dijkstra(source: Coords, grid: &Grid, rules: &Rules) -> HashMap<Coords, Visit> {
+    let mut visits = HashMap::from([(
+        source,
+        Visit {
+            cost: 0,
+            previous: None,
+        },
+    )]);
+    let mut to_visit = BinaryHeap::from([Reverse((0, source))]);
+    while let Some(Reverse((cost, point))) = to_visit.pop() {
+        if cost > visits[&point].cost {
+            // already reached more cheaply since this was queued.
+            continue;
+        }
+        for neighbor in get_neighbors(point, grid, rules) {
+            let cost = cost + rules.cost(&grid[&point], &grid[&neighbor]);
+            if visits.get(&neighbor).is_none_or(|v| cost < v.cost) {
+                visits.insert(
+                    neighbor,
+                    Visit {
+                        cost,
+                        previous: Some(point),
+                    },
+                );
+                to_visit.push(Reverse((cost, neighbor)));
+            }
+        }
+    }
+    visits
+}
+
+// Draws the route like the puzzle does, each step pointing to the next one.
+// Diagonal steps have no arrow, so they are drawn as the slash they follow.
+fn render_route(route: &[Coords], grid: &Grid) -> String {
+    let rows = grid.keys().map(|(x, _)| *x).max().unwrap_or(0);
+    let cols = grid.keys().map(|(_, y)| *y).max().unwrap_or(0);
+    let mut canvas = vec![vec!['.'; cols as usize + 1]; rows as usize + 1];
+    for step in route.windows(2) {
+        let ((x, y), (nx, ny)) = (step[0], step[1]);
+        canvas[x as usize][y as usize] = match (nx - x, ny - y) {
+            (-1, 0) => '^',
+            (1, 0) => 'v',
+            (0, -1) => '<',
+            (0, 1) => '>',
+            (-1, -1) | (1, 1) => '\\',
+            _ => '/',
+        };
+    }
+    if let Some((x, y)) = route.last() {
+        canvas[*x as usize][*y as usize] = 'E';
+    }
+    canvas
+        .iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn get_neighbors((x, y): Coords, grid: &Grid, rules: &Rules) -> Vec<Coords> {
     let mut neighbors = vec![];
     let point = &grid[&(x, y)];
-    for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+    for (dx, dy) in rules.offsets() {
         let candidate = (x + dx, y + dy);
         if let Some(n_point) = grid.get(&candidate) {
-            if n_point.elevation <= point.elevation + 1
-                && n_point.min_distance > point.min_distance + 1
-            {
+            if rules.can_move(point, n_point) {
                 neighbors.push(candidate)
             }
         }